@@ -0,0 +1,173 @@
+use chrono::{Days, NaiveDate};
+
+use crate::{
+    app::{App, CONFIG_PATH, TODO_LIST_PATH},
+    config::Config,
+    error::Result,
+    todo::{Deadline, MonthEnd, Todo, TodoKind, TodoState},
+};
+
+//按天分组的待办：过期的放在最前，没有日期的单独一组
+#[derive(Debug, Default)]
+pub struct Agenda<'a> {
+    pub expired: Vec<&'a Todo>,
+    pub days: Vec<(NaiveDate, Vec<&'a Todo>)>,
+    pub no_deadline: Vec<&'a Todo>,
+}
+
+impl<'a> Agenda<'a> {
+    //没有待办的日期不会出现在days中
+    pub fn new(
+        todo_list: &'a [Todo],
+        today: NaiveDate,
+        days: u32,
+        list: &str,
+        month_end: MonthEnd,
+    ) -> Self {
+        let todo_list = todo_list
+            .iter()
+            .filter(|todo| todo.list == list)
+            .collect::<Vec<_>>();
+        let expired = todo_list
+            .iter()
+            .filter(|todo| todo.state == TodoState::Expired)
+            .copied()
+            .collect();
+        let no_deadline = todo_list
+            .iter()
+            .filter(|todo| todo.state == TodoState::NoDeadline)
+            .copied()
+            .collect();
+        let days = today
+            .iter_days()
+            .take(days as usize)
+            .filter_map(|date| {
+                let mut items = todo_list
+                    .iter()
+                    .filter(|todo| {
+                        todo.state != TodoState::Expired && todo.kind.occurs_on(date, month_end)
+                    })
+                    .copied()
+                    .collect::<Vec<_>>();
+                items.sort_by_key(|todo| time_of(todo));
+                (!items.is_empty()).then_some((date, items))
+            })
+            .collect();
+        Agenda {
+            expired,
+            days,
+            no_deadline,
+        }
+    }
+
+    pub fn to_text(&self, today: NaiveDate) -> String {
+        let mut text = String::new();
+        let mut section = |title: String, items: &[&Todo]| {
+            text.push_str(&title);
+            text.push('\n');
+            for todo in items {
+                text.push_str(&format!("  {}\n", item_line(todo)));
+            }
+        };
+        if !self.expired.is_empty() {
+            section("Expired".to_string(), &self.expired);
+        }
+        for (date, items) in self.days.iter() {
+            section(day_title(*date, today), items);
+        }
+        if !self.no_deadline.is_empty() {
+            section("No deadline".to_string(), &self.no_deadline);
+        }
+        text
+    }
+}
+
+pub fn day_title(date: NaiveDate, today: NaiveDate) -> String {
+    let prefix = if date == today {
+        "Today, "
+    } else if Some(date) == today.checked_add_days(Days::new(1)) {
+        "Tomorrow, "
+    } else {
+        ""
+    };
+    format!("{}{}", prefix, date.format("%a %Y-%m-%d"))
+}
+
+pub fn item_line(todo: &Todo) -> String {
+    match (time_of(todo), &todo.kind) {
+        (Some(time), _) => format!("{} {}", time.format("%H:%M"), todo.text),
+        (None, TodoKind::Progress(progress)) => format!("{} ({})", todo.text, progress),
+        (None, TodoKind::Once(_) | TodoKind::General) => todo.text.clone(),
+        (None, kind) => format!("{} ({})", todo.text, kind.print_info()),
+    }
+}
+
+fn time_of(todo: &Todo) -> Option<chrono::NaiveTime> {
+    match todo.kind {
+        TodoKind::Once(Deadline { time, .. }) => time,
+        _ => None,
+    }
+}
+
+//命令行输出，不启动界面
+pub fn print_agenda(days: Option<u32>) -> Result<()> {
+    //配置有误时和界面一样提示后使用默认配置
+    let config = Config::load(&CONFIG_PATH).unwrap_or_else(|err| {
+        eprintln!("{}: ignored config: {}", env!("CARGO_PKG_NAME"), err);
+        Config::default()
+    });
+    //没有数据文件时输出空的日程，其他读取错误照常返回
+    let mut todo_list = App::read_json::<Vec<Todo>>(&TODO_LIST_PATH)?.unwrap_or_default();
+    todo_list
+        .iter_mut()
+        .for_each(|todo| todo.state_check(config.month_end));
+    let today = chrono::Local::now().date_naive();
+    let days = days.unwrap_or(config.agenda.days);
+    let agenda = Agenda::new(&todo_list, today, days, "", config.month_end);
+    print!("{}", agenda.to_text(today));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(input: &str, now: &str) -> Todo {
+        let now = chrono::NaiveDateTime::parse_from_str(now, "%Y-%m-%d %H:%M").unwrap();
+        let (text, kind) = Todo::input_parse(input, now.date()).unwrap();
+        let mut todo = Todo {
+            text,
+            kind,
+            ..Default::default()
+        };
+        todo.state_check_at(now, MonthEnd::Clamp);
+        todo
+    }
+
+    #[test]
+    fn agenda_test() {
+        // 2025-01-15 是星期三
+        let now = "2025-01-15 12:00";
+        let today = "2025-01-15".parse().unwrap();
+        let todo_list = [
+            todo("report -2025-01-10", now),
+            todo("standup -2025-01-15 09:00", now),
+            todo("review -2025-01-15 15:00", now),
+            todo("gym -fri", now),
+            todo("milk", now),
+            todo("read @3/10", now),
+            todo("trip -2025-02-01", now),
+        ];
+        let agenda = Agenda::new(&todo_list, today, 7, "", MonthEnd::Clamp);
+        assert_eq!(
+            agenda.to_text(today),
+            "Expired\n  report\n  09:00 standup\n\
+             Today, Wed 2025-01-15\n  15:00 review\n\
+             Fri 2025-01-17\n  gym (Week: Fri)\n\
+             No deadline\n  milk\n  read (3/10)\n"
+        );
+        assert!(Agenda::new(&todo_list, today, 7, "work", MonthEnd::Clamp)
+            .to_text(today)
+            .is_empty());
+    }
+}
@@ -1,20 +1,40 @@
 use crate::{
-    todo::{Todo, TodoKind, TodoState},
-    SyncState,
+    agenda::{self, Agenda},
+    calendar,
+    completion::Completion,
+    config::Config,
+    error::{Error, Result},
+    events::{CrosstermEvents, EventSource},
+    keymap::Keymap,
+    notice::{self, Level, NoticeLog},
+    palette, reminder,
+    stats::{self, DoneEntry},
+    sync::SyncResult,
+    theme::Theme,
+    todo::{Todo, TodoKind, TodoState, SYNTAX_EXAMPLES},
+    SyncAction, SyncState, SyncStatus,
 };
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    style::{Style, Stylize},
     symbols::border::{self, PLAIN},
-    text::Line,
-    widgets::{Block, Paragraph, Row, Table, TableState},
-    DefaultTerminal, Frame,
+    text::{Line, Span},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Cell, Clear, List, ListState, Paragraph, Row, Sparkline,
+        Table, TableState, Wrap,
+    },
+    DefaultTerminal, Frame, Terminal,
 };
+use serde::de::DeserializeOwned;
 use std::{
+    collections::{HashSet, VecDeque},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, LazyLock, RwLock},
+    time::{Duration, Instant},
 };
 use tui_input::{backend::crossterm::EventHandler, Input as InputBuffer};
 
@@ -31,11 +51,26 @@ pub static TODO_LIST_PATH: LazyLock<PathBuf> =
 pub static SYNC_STATE_PATH: LazyLock<PathBuf> =
     LazyLock::new(|| CURRENT_PATH.join("sync_state.json"));
 
-#[derive(Debug, Default, PartialEq)]
+pub static HISTORY_PATH: LazyLock<PathBuf> = LazyLock::new(|| CURRENT_PATH.join("history.json"));
+
+pub static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| CURRENT_PATH.join("config.json"));
+
+#[derive(Debug, Default, PartialEq, Clone)]
 pub enum InputMode {
     #[default]
     Normal,
     Insert,
+    Search,
+    Command,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum View {
+    #[default]
+    Table,
+    Calendar,
+    Agenda,
+    Stats,
 }
 
 #[derive(Debug, Default)]
@@ -48,22 +83,97 @@ pub struct App {
     pub input_mode: InputMode,
     pub sync_state: Arc<RwLock<SyncState>>,
     pub update_cache: Option<String>,
+    //同一个待办改了时间后重新提醒
+    pub reminded: HashSet<(String, NaiveDateTime)>,
+    //同时到时间的提醒依次显示
+    pub alerts: VecDeque<String>,
+    pub input_error: Option<String>,
+    pub completion: Option<Completion>,
+    pub keymap: Keymap,
+    //快照同时记录当时完成记录的条数，撤销时一并回退
+    pub undo_history: Vec<(Vec<Todo>, usize)>,
+    pub help_scroll: Option<u16>,
+    pub current_list: String,
+    pub palette_selected: usize,
+    pub theme: Theme,
+    pub view: View,
+    pub calendar_date: NaiveDate,
+    pub agenda_scroll: u16,
+    pub history: Vec<DoneEntry>,
+    //上一次渲染时表格和输入框的位置，用于处理鼠标点击
+    pub table_area: Rect,
+    pub input_area: Rect,
+    pub last_click: Option<(Instant, usize)>,
+    //多选标记的待办，用创建时间区分
+    pub marked: HashSet<String>,
+    //表格的显示顺序，不影响保存的顺序
+    sort_key: SortKey,
+    //为空时使用CURRENT_PATH，测试时指向临时目录
+    pub data_dir: Option<PathBuf>,
+    pub notices: NoticeLog,
+    pub log_scroll: Option<u16>,
+    pub sync_status: Arc<RwLock<SyncStatus>>,
+    //后台同步成功后的结果，由主循环取出并应用
+    pub sync_result: Arc<RwLock<Option<SyncResult>>>,
+    pub config: Config,
+    pub last_sync: Option<Instant>,
+    //上次开始同步之后是否有修改
+    pub changed_at: Option<Instant>,
+    //同步期间有修改时，同步结束后马上再同步一次
+    pub resync_pending: bool,
+    pub quitting: bool,
+    pub quit_unsaved: bool,
+    //上一次渲染时表格显示的列
+    table_columns: Vec<Column>,
 }
 
-enum Message {
+#[derive(Debug, Clone)]
+pub(crate) enum Message {
     Add,
+    AddText(String),
     Delete,
+    Done,
+    Mark,
+    MarkAll,
+    ClearMarks,
+    Tag(String, bool),
+    MoveTo(String),
+    ChangeKind(String),
     Save,
     Rewrite,
+    Undo,
     Filter(FilterType),
     InputModeChange(InputMode),
+    CancelSearch,
+    RunCommand,
+    Sort(SortKey),
+    Reorder(Reorder),
+    SwitchList(String),
+    Export(String),
     SelectPrevious,
     SelectNext,
+    SelectFirst,
+    Select(usize),
+    SelectLast,
+    StepProgress(bool),
+    DismissAlert,
+    ToggleHelp,
+    SwitchView(View),
+    MoveDay(i64),
+    MoveMonth(i32),
+    ScrollAgenda(i16),
+    SelectDay,
+    ScrollHelp(i16),
+    ToggleLog,
+    ScrollLog(i16),
+    Complete(bool),
+    AcceptCompletion,
     Sync,
     Quit,
 }
 
-enum FilterType {
+#[derive(Debug, Clone)]
+pub(crate) enum FilterType {
     All,
     Expired,
     InProgress,
@@ -72,20 +182,117 @@ enum FilterType {
     Week,
     Month,
     Once,
+    Recur,
     Progress,
     General,
+    Search(String),
+    Day(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Reorder {
+    Up,
+    Down,
+    Top,
+    Bottom,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum SortKey {
+    //保存的顺序，也就是手动调整的顺序
+    #[default]
+    Manual,
+    Text,
+    Kind,
+    State,
+    Next,
+    Created,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Index,
+    Content,
+    Kind,
+    State,
+    Next,
+    CreatedAt,
+}
+
+impl Column {
+    //表格越窄去掉的列越多，最窄时只剩内容一列，类型跟在内容后面
+    fn for_width(width: u16) -> Vec<(Column, Constraint)> {
+        let kind = match width {
+            0..70 => Constraint::Percentage(30),
+            _ => Constraint::Percentage(20),
+        };
+        let mut columns = vec![
+            (Column::Index, Constraint::Length(5)),
+            (Column::Content, Constraint::Fill(1)),
+            (Column::Kind, kind),
+            (Column::State, Constraint::Length(10)),
+            (Column::Next, Constraint::Length(10)),
+            (Column::CreatedAt, Constraint::Length(19)),
+        ];
+        let hidden: &[Column] = match width {
+            0..45 => &[
+                Column::Index,
+                Column::Kind,
+                Column::State,
+                Column::Next,
+                Column::CreatedAt,
+            ],
+            45..70 => &[Column::Index, Column::Next, Column::CreatedAt],
+            70..100 => &[Column::CreatedAt],
+            _ => &[],
+        };
+        columns.retain(|(column, _)| !hidden.contains(column));
+        columns
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Index => "Index",
+            Column::Content => "Content",
+            Column::Kind => "Kind",
+            Column::State => "State",
+            Column::Next => "Next",
+            Column::CreatedAt => "CreatedAt",
+        }
+    }
+
+    fn sort_key(&self) -> SortKey {
+        match self {
+            Column::Index => SortKey::Manual,
+            Column::CreatedAt => SortKey::Created,
+            Column::Content => SortKey::Text,
+            Column::Kind => SortKey::Kind,
+            Column::State => SortKey::State,
+            Column::Next => SortKey::Next,
+        }
+    }
 }
 
 impl App {
-    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        self.run_with(terminal, &mut CrosstermEvents)
+    }
+
+    pub fn run_with<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut impl EventSource,
+    ) -> Result<()> {
         //初始化
-        self.init();
+        self.init()?;
         //主循环
         while !self.exit {
+            //刷新待办状态并检查提醒
+            self.tick();
             //根据数据渲染页面
             terminal.draw(|frame| self.view(frame))?;
             //根据用户事件生成消息
-            let mut current_msg = self.handle_events()?;
+            let mut current_msg = self.handle_events(events)?;
             //根据消息更新数据
             while let Some(msg) = current_msg {
                 current_msg = self.update(msg);
@@ -94,24 +301,27 @@ impl App {
         Ok(())
     }
 
-    fn init(&mut self) {
-        if !CURRENT_PATH.exists() {
-            std::fs::create_dir(CURRENT_PATH.as_path()).unwrap();
-        }
-        if !TODO_LIST_PATH.exists() {
-            std::fs::File::create(TODO_LIST_PATH.as_path()).unwrap();
+    fn init(&mut self) -> Result<()> {
+        let data_dir = self.data_dir.clone().unwrap_or(CURRENT_PATH.to_path_buf());
+        std::fs::create_dir_all(&data_dir).map_err(|err| Error::storage(data_dir, err))?;
+        let config_path = self.data_path(&CONFIG_PATH);
+        if !config_path.exists() {
+            let config_file = std::fs::File::create(&config_path)
+                .map_err(|err| Error::storage(&config_path, err))?;
+            serde_json::to_writer_pretty(config_file, &Config::default())
+                .map_err(|err| Error::storage(&config_path, err))?;
         }
-        if !SYNC_STATE_PATH.exists() {
-            std::fs::File::create(SYNC_STATE_PATH.as_path()).unwrap();
-        }
-        self.load_todo_list();
+        self.config = self.load_config(&config_path);
+        self.load_todo_list()?;
         self.app_info = App::get_app_info();
-        self.todo_list
-            .write()
-            .unwrap()
-            .iter_mut()
-            .for_each(Todo::state_check);
-        self.sync_data();
+        self.keymap = Keymap::new(&self.config.keymap);
+        self.theme = Theme::new(&self.config.theme);
+        //不在启动时同步的话，定时同步从启动时开始计时
+        match self.config.sync.on_start {
+            true => self.sync_data(),
+            false => self.last_sync = Some(Instant::now()),
+        }
+        Ok(())
     }
     //view方法只负责渲染，尽量不要在这里修改全局数据，启用可变引用只是为了满足状态渲染函数的参数要求
     fn view(&mut self, frame: &mut Frame) {
@@ -121,6 +331,47 @@ impl App {
             .split(frame.area());
         self.render_msg_bar(frame, layout[0]);
         self.render_todo_window(frame, layout[1]);
+        if let Some(scroll) = self.help_scroll {
+            self.render_help(frame, scroll);
+        }
+        if let Some(scroll) = self.log_scroll {
+            self.render_log(frame, scroll);
+        }
+        if let Some(alert) = self.alerts.front() {
+            self.render_alert(frame, alert);
+        }
+    }
+
+    fn tick(&mut self) {
+        self.poll_sync(Instant::now());
+        self.tick_at(chrono::Local::now().naive_local());
+    }
+
+    fn tick_at(&mut self, now: NaiveDateTime) {
+        let mut todo_list = self.todo_list.write().unwrap();
+        let month_end = self.config.month_end;
+        todo_list
+            .iter_mut()
+            .for_each(|todo| todo.state_check_at(now, month_end));
+        let reminder = &self.config.reminder;
+        if !reminder.enabled {
+            return;
+        }
+        for todo in todo_list.iter() {
+            let Some(remind_at) = todo.remind_at(reminder.minutes_before) else {
+                continue;
+            };
+            if remind_at <= now
+                && todo.state != TodoState::Expired
+                && self.reminded.insert((todo.created_at.clone(), remind_at))
+            {
+                self.alerts
+                    .push_back(format!("{} ({})", todo.text, todo.kind.print_info()));
+                if let Some(ref command) = reminder.command {
+                    reminder::run_command(command, todo);
+                }
+            }
+        }
     }
 
     fn update(&mut self, msg: Message) -> Option<Message> {
@@ -128,94 +379,440 @@ impl App {
             Message::Add => {
                 let input = self.input_buffer.value();
                 if !input.is_empty() {
-                    let todo = Todo::new(input);
-                    self.todo_list.write().unwrap().push(todo);
-                    if let Some(ref created_at) = self.update_cache {
-                        self.todo_list
-                            .write()
-                            .unwrap()
-                            .retain(|todo| todo.created_at != *created_at);
-                        self.update_cache = None;
+                    let mut todo = match Todo::new(input) {
+                        Ok(todo) => todo,
+                        Err(err) => {
+                            //解析失败时保留输入内容，方便修改
+                            self.input_warning(err.to_string());
+                            return None;
+                        }
+                    };
+                    todo.state_check(self.config.month_end);
+                    //改写时原地替换，保留位置、创建时间和所属清单
+                    let index = self.update_cache.take().and_then(|created_at| {
+                        let todo_list = self.todo_list.read().unwrap();
+                        todo_list
+                            .iter()
+                            .position(|todo| todo.created_at == created_at)
+                    });
+                    self.push_undo_history();
+                    match index {
+                        Some(index) => {
+                            let mut todo_list = self.todo_list.write().unwrap();
+                            todo.created_at = todo_list[index].created_at.clone();
+                            todo.list = todo_list[index].list.clone();
+                            todo_list[index] = todo.clone();
+                        }
+                        None => {
+                            todo.list = self.current_list.clone();
+                            self.make_unique_id(&mut todo);
+                            self.todo_list.write().unwrap().push(todo.clone());
+                        }
                     }
+                    let created_at = todo.created_at;
                     self.input_buffer.reset();
-                    self.table_state.select_last();
+                    self.select_id(&created_at);
                 }
                 Some(Message::Save)
             }
+            Message::AddText(text) => {
+                self.input_buffer = InputBuffer::new(text);
+                Some(Message::Add)
+            }
             Message::Save => {
                 {
                     let mut sync_state = self.sync_state.write().unwrap();
                     sync_state.last_save_at = chrono::Local::now().naive_local();
                 }
-                self.save_todo_list();
+                self.changed_at = Some(Instant::now());
+                //保存会清除过滤，按待办重新定位选中的行
+                let selected = self.selected_id();
+                let result = self.save_todo_list();
+                self.report(result);
+                match selected {
+                    Some(created_at) => self.select_id(&created_at),
+                    None => self.clamp_selection(),
+                }
                 None
             }
             Message::Delete => {
-                if let Some(index) = self.table_state.selected() {
-                    self.todo_list.write().unwrap().remove(index);
+                let targets = self.targets();
+                if targets.is_empty() {
+                    return None;
                 }
+                self.push_undo_history();
+                self.todo_list
+                    .write()
+                    .unwrap()
+                    .retain(|todo| !targets.contains(&todo.created_at));
+                self.marked.clear();
                 Some(Message::Save)
             }
-            Message::Rewrite => {
-                if let Some(index) = self.table_state.selected() {
-                    let todo: Todo;
-                    {
-                        let todo_list = self.todo_list.read().unwrap();
-                        todo = todo_list
-                            .iter()
-                            .filter(|todo| !todo.is_hidden)
-                            .cloned()
-                            .nth(index)
-                            .unwrap();
+            Message::Undo => match self.undo_history.pop() {
+                Some((todo_list, history_len)) => {
+                    *self.todo_list.write().unwrap() = todo_list;
+                    if history_len != self.history.len() {
+                        self.history.truncate(history_len);
+                        self.report(self.save_history());
                     }
-                    let value = match todo.kind {
-                        TodoKind::General => todo.text.clone(),
-                        TodoKind::Week(week) => format!("{} - {}", todo.text, week),
-                        TodoKind::Month(day) => format!("{} - {}", todo.text, day),
-                        TodoKind::Once(date) => format!("{} - {}", todo.text, date),
-                        TodoKind::Progress(ref progress) => format!("{} @ {}", todo.text, progress),
-                    };
+                    Some(Message::Save)
+                }
+                None => None,
+            },
+            //一次性的待办完成后移除，周期性的保留并记录这一次完成
+            Message::Done => {
+                let targets = self.targets();
+                if targets.is_empty() {
+                    return None;
+                }
+                self.push_undo_history();
+                let now = chrono::Local::now().naive_local();
+                let month_end = self.config.month_end;
+                self.todo_list.write().unwrap().retain(|todo| {
+                    if !targets.contains(&todo.created_at) {
+                        return true;
+                    }
+                    self.history.push(DoneEntry::new(todo, now, month_end));
+                    !matches!(
+                        todo.kind,
+                        TodoKind::General | TodoKind::Progress(_) | TodoKind::Once(_)
+                    )
+                });
+                self.report(self.save_history());
+                self.marked.clear();
+                Some(Message::Save)
+            }
+            Message::Mark => {
+                if let Some(created_at) = self.selected_id() {
+                    if !self.marked.remove(&created_at) {
+                        self.marked.insert(created_at);
+                    }
+                }
+                Some(Message::SelectNext)
+            }
+            //已经全部标记时再次执行则取消标记
+            Message::MarkAll => {
+                let visible = self.visible_indices();
+                let todo_list = self.todo_list.read().unwrap();
+                let visible = visible
+                    .into_iter()
+                    .map(|index| todo_list[index].created_at.clone())
+                    .collect::<HashSet<_>>();
+                drop(todo_list);
+                if visible.is_subset(&self.marked) {
+                    self.marked
+                        .retain(|created_at| !visible.contains(created_at));
+                } else {
+                    self.marked.extend(visible);
+                }
+                None
+            }
+            Message::ClearMarks => {
+                self.marked.clear();
+                None
+            }
+            Message::Tag(tag, add) => {
+                let tag = match tag.strip_prefix('#') {
+                    Some(_) => tag,
+                    None => format!("#{}", tag),
+                };
+                self.bulk_edit(|todo| {
+                    let mut words = todo
+                        .text
+                        .split_whitespace()
+                        .filter(|word| *word != tag)
+                        .collect::<Vec<_>>();
+                    if add {
+                        words.push(&tag);
+                    }
+                    todo.text = words.join(" ");
+                })
+            }
+            Message::MoveTo(list) => self.bulk_edit(|todo| todo.list = list.clone()),
+            Message::ChangeKind(suffix) => match Todo::parse_kind(&suffix) {
+                Ok(kind) => {
+                    let month_end = self.config.month_end;
+                    self.bulk_edit(|todo| {
+                        todo.kind = kind.clone();
+                        todo.state_check(month_end);
+                    })
+                }
+                Err(err) => {
+                    self.input_warning(err.to_string());
+                    None
+                }
+            },
+            Message::Rewrite => {
+                if let Some(index) = self.selected_index() {
+                    let todo = self.todo_list.read().unwrap()[index].clone();
+                    let value = todo.to_input();
                     self.input_buffer = self.input_buffer.clone().with_value(value);
                     self.input_mode = InputMode::Insert;
                     self.update_cache = Some(todo.created_at.clone());
                 }
                 None
             }
+            Message::Complete(forward) => {
+                match self.completion {
+                    Some(ref mut completion) => completion.select(forward),
+                    None => {
+                        let todo_list = self.todo_list.read().unwrap();
+                        self.completion = Completion::new(
+                            self.input_buffer.value(),
+                            self.input_buffer.cursor(),
+                            &todo_list,
+                        );
+                        //只有一个候选项时直接补全
+                        if self
+                            .completion
+                            .as_ref()
+                            .is_some_and(|completion| completion.candidates.len() == 1)
+                        {
+                            return Some(Message::AcceptCompletion);
+                        }
+                    }
+                }
+                None
+            }
+            Message::AcceptCompletion => {
+                if let Some(completion) = self.completion.take() {
+                    let (value, cursor) =
+                        completion.apply(self.input_buffer.value(), self.input_buffer.cursor());
+                    self.input_buffer = InputBuffer::new(value).with_cursor(cursor);
+                }
+                None
+            }
+            Message::RunCommand => {
+                let input = self.input_buffer.value().to_string();
+                let msg = match palette::parse(&input) {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        //输入的不是完整命令时使用选中的候选项
+                        let suggestions = palette::suggestions(&input, &self.list_names());
+                        match suggestions.get(self.palette_selected) {
+                            Some((replacement, _)) if *replacement != input => {
+                                self.palette_selected = 0;
+                                self.input_buffer = InputBuffer::new(replacement.clone());
+                                if replacement.ends_with(' ') {
+                                    return None;
+                                }
+                                return Some(Message::RunCommand);
+                            }
+                            _ => {
+                                self.input_warning(err);
+                                return None;
+                            }
+                        }
+                    }
+                };
+                self.input_buffer.reset();
+                self.input_mode = InputMode::Normal;
+                self.palette_selected = 0;
+                Some(msg)
+            }
+            //排序只改变显示的顺序，不修改保存的列表
+            Message::Sort(sort_key) => {
+                let selected = self.selected_id();
+                self.sort_key = sort_key;
+                if let Some(created_at) = selected {
+                    self.select_id(&created_at);
+                }
+                None
+            }
+            //只在当前可见的待办之间移动，顺序直接体现在保存和同步的列表中
+            //排序显示时先回到手动调整的顺序，选中的待办不变
+            Message::Reorder(reorder) => {
+                if self.sort_key != SortKey::Manual {
+                    let selected = self.selected_id();
+                    self.sort_key = SortKey::Manual;
+                    if let Some(created_at) = selected {
+                        self.select_id(&created_at);
+                    }
+                }
+                let from = self.table_state.selected()?;
+                let visible = self.visible_indices();
+                let to = match reorder {
+                    Reorder::Up => from.checked_sub(1)?,
+                    Reorder::Down => from + 1,
+                    Reorder::Top => 0,
+                    Reorder::Bottom => visible.len().checked_sub(1)?,
+                };
+                if to == from || from >= visible.len() || to >= visible.len() {
+                    return None;
+                }
+                self.push_undo_history();
+                let mut todo_list = self.todo_list.write().unwrap();
+                let todo = todo_list.remove(visible[from]);
+                todo_list.insert(visible[to], todo);
+                drop(todo_list);
+                self.table_state.select(Some(to));
+                Some(Message::Save)
+            }
+            Message::SwitchList(list) => {
+                self.current_list = list;
+                self.marked.clear();
+                self.table_state.select(None);
+                Some(Message::Filter(FilterType::All))
+            }
+            Message::Export(path) => {
+                match self.export_todo_list(&path) {
+                    Ok(()) => self.notify(Level::Info, format!("Exported to {}", path)),
+                    Err(err) => {
+                        self.input_error = Some(format!("export failed: {}", err));
+                        self.notify(Level::Error, format!("Export failed: {}", err));
+                    }
+                }
+                None
+            }
+            Message::CancelSearch => {
+                self.input_buffer.reset();
+                self.input_mode = InputMode::Normal;
+                Some(Message::Filter(FilterType::All))
+            }
             Message::InputModeChange(input_mode) => {
-                if input_mode == InputMode::Normal {
+                self.input_error = None;
+                self.completion = None;
+                self.palette_selected = 0;
+                if input_mode != InputMode::Insert {
                     self.input_buffer.reset();
                     self.update_cache = None;
                 }
                 self.input_mode = input_mode;
                 None
             }
+            //退出前同步时等待同步结束，再按一次直接退出
+            //保存失败时先不退出，再按一次放弃修改直接退出
             Message::Quit => {
-                //TODO询问是否同步
-                self.save_todo_list();
+                if let Err(err) = self.save_todo_list() {
+                    if !self.quit_unsaved {
+                        self.quit_unsaved = true;
+                        self.notify(
+                            Level::Error,
+                            format!("Save failed: {}, quit again to discard changes", err),
+                        );
+                        return None;
+                    }
+                }
+                if self.config.sync.on_quit && !self.quitting {
+                    self.quitting = true;
+                    self.start_sync();
+                    return None;
+                }
                 self.exit = true;
                 None
             }
+            //上下移动时在首尾循环，没有选中时从另一端开始
             Message::SelectPrevious => {
-                if let Some(index) = self.table_state.selected() {
-                    if index == 0 {
-                        self.table_state.select_last();
-                    } else {
-                        self.table_state.select_previous();
-                    }
-                } else {
-                    self.table_state.select_first();
+                let len = self.visible_indices().len();
+                if len > 0 {
+                    let index = match self.table_state.selected() {
+                        Some(index) if index > 0 && index < len => index - 1,
+                        _ => len - 1,
+                    };
+                    self.table_state.select(Some(index));
+                }
+                None
+            }
+            Message::Select(index) => {
+                if index < self.visible_indices().len() {
+                    self.table_state.select(Some(index));
+                }
+                None
+            }
+            Message::SelectFirst => {
+                if !self.visible_indices().is_empty() {
+                    self.table_state.select(Some(0));
                 }
                 None
             }
+            Message::SelectLast => {
+                let len = self.visible_indices().len();
+                self.table_state.select(len.checked_sub(1));
+                None
+            }
             Message::SelectNext => {
-                if let Some(index) = self.table_state.selected() {
-                    if index + 1 == self.todo_list.read().unwrap().len() {
-                        self.table_state.select_first();
-                    } else {
-                        self.table_state.select_next();
-                    }
-                } else {
-                    self.table_state.select_last();
+                let len = self.visible_indices().len();
+                if len > 0 {
+                    let index = match self.table_state.selected() {
+                        Some(index) if index + 1 < len => index + 1,
+                        Some(_) => 0,
+                        None => len - 1,
+                    };
+                    self.table_state.select(Some(index));
+                }
+                None
+            }
+            //只有进度类型的待办需要记录撤销和保存
+            Message::StepProgress(forward) => {
+                let index = self.selected_index()?;
+                if !matches!(
+                    self.todo_list.read().unwrap()[index].kind,
+                    TodoKind::Progress(_)
+                ) {
+                    return None;
+                }
+                self.push_undo_history();
+                self.todo_list.write().unwrap()[index].progress_step(forward);
+                Some(Message::Save)
+            }
+            Message::DismissAlert => {
+                self.alerts.pop_front();
+                None
+            }
+            Message::ToggleHelp => {
+                self.help_scroll = match self.help_scroll {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+                None
+            }
+            //再次切换到当前视图时回到表格
+            Message::SwitchView(view) => {
+                self.view = if self.view == view { View::Table } else { view };
+                if self.view == View::Calendar {
+                    self.calendar_date = chrono::Local::now().date_naive();
+                }
+                self.agenda_scroll = 0;
+                None
+            }
+            Message::ScrollAgenda(step) => {
+                self.agenda_scroll = self.agenda_scroll.saturating_add_signed(step);
+                None
+            }
+            Message::MoveDay(days) => {
+                self.calendar_date = self
+                    .calendar_date
+                    .checked_add_signed(chrono::TimeDelta::days(days))
+                    .unwrap_or(self.calendar_date);
+                None
+            }
+            Message::MoveMonth(months) => {
+                self.calendar_date = calendar::shift_month(self.calendar_date, months);
+                None
+            }
+            Message::SelectDay => {
+                self.view = View::Table;
+                self.table_state.select(Some(0));
+                Some(Message::Filter(FilterType::Day(self.calendar_date)))
+            }
+            Message::ScrollHelp(step) => {
+                let max_scroll = self.help_lines().len() as u16 - 1;
+                if let Some(ref mut scroll) = self.help_scroll {
+                    *scroll = scroll.saturating_add_signed(step).min(max_scroll);
+                }
+                None
+            }
+            Message::ToggleLog => {
+                self.log_scroll = match self.log_scroll {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+                None
+            }
+            Message::ScrollLog(step) => {
+                let max_scroll = self.notices.read().unwrap().len().saturating_sub(1) as u16;
+                if let Some(ref mut scroll) = self.log_scroll {
+                    *scroll = scroll.saturating_add_signed(step).min(max_scroll);
                 }
                 None
             }
@@ -231,47 +828,32 @@ impl App {
                     }
                     FilterType::General => {
                         todo_lsit.iter_mut().for_each(|todo| {
-                            todo.is_hidden = if let TodoKind::General = todo.kind {
-                                false
-                            } else {
-                                true
-                            };
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::General);
                         });
                     }
                     FilterType::Week => {
                         todo_lsit.iter_mut().for_each(|todo| {
-                            todo.is_hidden = if let TodoKind::Week(_) = todo.kind {
-                                false
-                            } else {
-                                true
-                            };
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::Week(_));
                         });
                     }
                     FilterType::Month => {
                         todo_lsit.iter_mut().for_each(|todo| {
-                            todo.is_hidden = if let TodoKind::Month(_) = todo.kind {
-                                false
-                            } else {
-                                true
-                            };
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::Month(_));
                         });
                     }
                     FilterType::Once => {
                         todo_lsit.iter_mut().for_each(|todo| {
-                            todo.is_hidden = if let TodoKind::Once(_) = todo.kind {
-                                false
-                            } else {
-                                true
-                            };
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::Once(_));
+                        });
+                    }
+                    FilterType::Recur => {
+                        todo_lsit.iter_mut().for_each(|todo| {
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::Recur(_));
                         });
                     }
                     FilterType::Progress => {
                         todo_lsit.iter_mut().for_each(|todo| {
-                            todo.is_hidden = if let TodoKind::Progress(_) = todo.kind {
-                                false
-                            } else {
-                                true
-                            };
+                            todo.is_hidden = !matches!(todo.kind, TodoKind::Progress(_));
                         });
                     }
                     FilterType::Expired => {
@@ -294,21 +876,102 @@ impl App {
                             todo.is_hidden = !(todo.state == TodoState::NoDeadline);
                         });
                     }
+                    FilterType::Day(date) => {
+                        todo_lsit.iter_mut().for_each(|todo| {
+                            todo.is_hidden = !todo.kind.occurs_on(date, self.config.month_end);
+                        });
+                    }
+                    FilterType::Search(query) => {
+                        let query = query.to_lowercase();
+                        todo_lsit.iter_mut().for_each(|todo| {
+                            todo.is_hidden = !todo.text.to_lowercase().contains(&query);
+                        });
+                    }
                 }
+                drop(todo_lsit);
+                self.clamp_selection();
                 None
             }
         }
     }
-    fn handle_events(&mut self) -> io::Result<Option<Message>> {
+    fn handle_events(&mut self, events: &mut impl EventSource) -> io::Result<Option<Message>> {
+        //无论哪种模式都不能阻塞，否则提醒无法及时弹出
+        if !events.poll(Duration::from_millis(250))? {
+            return Ok(None);
+        }
+        let event = events.read()?;
+        if !self.alerts.is_empty() {
+            return match event {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    Ok(Some(Message::DismissAlert))
+                }
+                _ => Ok(None),
+            };
+        }
+        if self.help_scroll.is_some() {
+            return match event {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    Ok(match key_event.code {
+                        KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => {
+                            Some(Message::ToggleHelp)
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => Some(Message::ScrollHelp(1)),
+                        KeyCode::Up | KeyCode::Char('k') => Some(Message::ScrollHelp(-1)),
+                        KeyCode::PageDown => Some(Message::ScrollHelp(10)),
+                        KeyCode::PageUp => Some(Message::ScrollHelp(-10)),
+                        KeyCode::Home => Some(Message::ScrollHelp(i16::MIN)),
+                        _ => None,
+                    })
+                }
+                _ => Ok(None),
+            };
+        }
+        if self.log_scroll.is_some() {
+            return match event {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    Ok(match key_event.code {
+                        KeyCode::Esc | KeyCode::Char('L') | KeyCode::Char('q') => {
+                            Some(Message::ToggleLog)
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => Some(Message::ScrollLog(1)),
+                        KeyCode::Up | KeyCode::Char('k') => Some(Message::ScrollLog(-1)),
+                        KeyCode::PageDown => Some(Message::ScrollLog(10)),
+                        KeyCode::PageUp => Some(Message::ScrollLog(-10)),
+                        KeyCode::Home => Some(Message::ScrollLog(i16::MIN)),
+                        _ => None,
+                    })
+                }
+                _ => Ok(None),
+            };
+        }
         if let InputMode::Insert = self.input_mode {
-            match event::read()? {
+            match event {
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    //补全菜单打开时优先处理菜单操作
+                    if self.completion.is_some() {
+                        match key_event.code {
+                            KeyCode::Tab | KeyCode::Down => {
+                                return Ok(Some(Message::Complete(true)))
+                            }
+                            KeyCode::BackTab | KeyCode::Up => {
+                                return Ok(Some(Message::Complete(false)))
+                            }
+                            KeyCode::Enter => return Ok(Some(Message::AcceptCompletion)),
+                            KeyCode::Esc => {
+                                self.completion = None;
+                                return Ok(None);
+                            }
+                            _ => self.completion = None,
+                        }
+                    }
                     match key_event.code {
                         KeyCode::Esc => {
                             return Ok(Some(Message::InputModeChange(InputMode::Normal)))
                         }
                         KeyCode::Enter => return Ok(Some(Message::Add)),
+                        KeyCode::Tab => return Ok(Some(Message::Complete(true))),
                         _ => {
+                            self.input_error = None;
                             self.input_buffer.handle_event(&Event::Key(key_event));
                         }
                     }
@@ -317,150 +980,1032 @@ impl App {
             }
             return Ok(None);
         }
-        if event::poll(std::time::Duration::from_millis(250))? {
-            match event::read()? {
+        if let InputMode::Command = self.input_mode {
+            match event {
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                    let msg = match key_event.code {
-                        KeyCode::Char('q') => Some(Message::Quit), //TODO 大写也要考虑
-                        KeyCode::Char('d') => Some(Message::Delete),
-                        KeyCode::Char('s') => Some(Message::Sync),
-                        KeyCode::Char('g') => Some(Message::Filter(FilterType::General)),
-                        KeyCode::Char('w') => Some(Message::Filter(FilterType::Week)),
-                        KeyCode::Char('m') => Some(Message::Filter(FilterType::Month)),
-                        KeyCode::Char('o') => Some(Message::Filter(FilterType::Once)),
-                        KeyCode::Char('p') => Some(Message::Filter(FilterType::Progress)),
-                        KeyCode::Char('a') => Some(Message::Filter(FilterType::All)),
-                        KeyCode::Char('i') => Some(Message::Filter(FilterType::InProgress)),
-                        KeyCode::Char('e') => Some(Message::Filter(FilterType::Expired)),
-                        KeyCode::Char('u') => Some(Message::Filter(FilterType::UpComing)),
-                        KeyCode::Char('n') => Some(Message::Filter(FilterType::NoDeadline)),
-                        KeyCode::Char('r') => Some(Message::Rewrite),
-                        KeyCode::Enter if self.input_mode != InputMode::Insert => {
-                            Some(Message::InputModeChange(InputMode::Insert))
+                    let suggestions =
+                        palette::suggestions(self.input_buffer.value(), &self.list_names());
+                    match key_event.code {
+                        KeyCode::Esc => {
+                            return Ok(Some(Message::InputModeChange(InputMode::Normal)))
                         }
-                        KeyCode::Up => Some(Message::SelectPrevious),
-                        KeyCode::Down => Some(Message::SelectNext),
+                        KeyCode::Enter => return Ok(Some(Message::RunCommand)),
+                        KeyCode::Down | KeyCode::Tab if !suggestions.is_empty() => {
+                            self.palette_selected = (self.palette_selected + 1) % suggestions.len();
+                        }
+                        KeyCode::Up | KeyCode::BackTab if !suggestions.is_empty() => {
+                            self.palette_selected =
+                                (self.palette_selected + suggestions.len() - 1) % suggestions.len();
+                        }
+                        _ => {
+                            self.input_error = None;
+                            self.palette_selected = 0;
+                            self.input_buffer.handle_event(&Event::Key(key_event));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+        if let InputMode::Search = self.input_mode {
+            //搜索时每输入一个字符就重新过滤一次
+            match event {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    return Ok(match key_event.code {
+                        KeyCode::Esc => Some(Message::CancelSearch),
+                        KeyCode::Enter => Some(Message::InputModeChange(InputMode::Normal)),
+                        _ => {
+                            self.input_buffer.handle_event(&Event::Key(key_event));
+                            let query = self.input_buffer.value().to_string();
+                            Some(Message::Filter(FilterType::Search(query)))
+                        }
+                    });
+                }
+                _ => return Ok(None),
+            }
+        }
+        match event {
+            Event::Mouse(mouse_event) => Ok(self.handle_mouse(mouse_event)),
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                //日历视图中方向键用来选择日期，其他按键仍然走快捷键
+                if self.view == View::Calendar {
+                    let msg = match key_event.code {
+                        KeyCode::Left | KeyCode::Char('h') => Some(Message::MoveDay(-1)),
+                        KeyCode::Right | KeyCode::Char('l') => Some(Message::MoveDay(1)),
+                        KeyCode::Up | KeyCode::Char('k') => Some(Message::MoveDay(-7)),
+                        KeyCode::Down | KeyCode::Char('j') => Some(Message::MoveDay(7)),
+                        KeyCode::PageUp | KeyCode::Char('[') => Some(Message::MoveMonth(-1)),
+                        KeyCode::PageDown | KeyCode::Char(']') => Some(Message::MoveMonth(1)),
+                        KeyCode::Enter => Some(Message::SelectDay),
+                        KeyCode::Esc => Some(Message::SwitchView(View::Calendar)),
                         _ => None,
                     };
-                    return Ok(msg);
+                    if msg.is_some() {
+                        return Ok(msg);
+                    }
                 }
-                _ => {}
+                if self.view == View::Stats && key_event.code == KeyCode::Esc {
+                    return Ok(Some(Message::SwitchView(View::Stats)));
+                }
+                if self.view == View::Agenda {
+                    let msg = match key_event.code {
+                        KeyCode::Up | KeyCode::Char('k') => Some(Message::ScrollAgenda(-1)),
+                        KeyCode::Down | KeyCode::Char('j') => Some(Message::ScrollAgenda(1)),
+                        KeyCode::PageUp => Some(Message::ScrollAgenda(-10)),
+                        KeyCode::PageDown => Some(Message::ScrollAgenda(10)),
+                        KeyCode::Esc => Some(Message::SwitchView(View::Agenda)),
+                        _ => None,
+                    };
+                    if msg.is_some() {
+                        return Ok(msg);
+                    }
+                }
+                Ok(self.keymap.handle(key_event))
             }
+            _ => Ok(None),
         }
-        Ok(None)
     }
 
-    fn render_msg_bar(&mut self, frame: &mut Frame, rect: Rect) {
-        let sync_state = self.sync_state.read().unwrap();
-        let msg = Line::from(vec![
-            (&self.app_info).into(),
-            " | ".into(),
-            format!(
-                "last save at: {}",
-                sync_state.last_save_at.format("%Y-%m-%d %H:%M:%S")
-            )
-            .into(),
-            " | ".into(),
-            format!(
-                "last sync at: {}",
-                sync_state.last_sync_at.format("%Y-%m-%d %H:%M:%S")
-            )
-            .into(),
-        ])
-        .centered();
-        frame.render_widget(msg, rect);
+    //只在普通模式的表格视图中响应鼠标
+    fn handle_mouse(&mut self, mouse_event: MouseEvent) -> Option<Message> {
+        if self.view != View::Table {
+            return None;
+        }
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown => Some(Message::SelectNext),
+            MouseEventKind::ScrollUp => Some(Message::SelectPrevious),
+            MouseEventKind::Down(MouseButton::Left) if self.input_area.contains(position) => {
+                Some(Message::InputModeChange(InputMode::Insert))
+            }
+            MouseEventKind::Down(MouseButton::Left) if self.table_area.contains(position) => {
+                //边框占一行，表头上下各有一行空白，只有一列时没有表头
+                let header_y = self.table_area.y + 2;
+                let first_row_y = match self.table_columns.len() {
+                    0 | 1 => self.table_area.y + 1,
+                    _ => header_y + 2,
+                };
+                //再次点击同一列时回到手动调整的顺序
+                if position.y == header_y && first_row_y > header_y {
+                    return self.header_sort_key(position.x).map(|sort_key| {
+                        match sort_key == self.sort_key {
+                            true => Message::Sort(SortKey::Manual),
+                            false => Message::Sort(sort_key),
+                        }
+                    });
+                }
+                let row = position.y.checked_sub(first_row_y)? as usize;
+                let index = row + self.table_state.offset();
+                //点在最后一行下面的空白处
+                if index >= self.visible_indices().len() {
+                    return None;
+                }
+                let now = Instant::now();
+                let double_click = self.last_click.is_some_and(|(at, last)| {
+                    last == index && now.duration_since(at) < Duration::from_millis(400)
+                });
+                self.last_click = Some((now, index));
+                if double_click {
+                    self.last_click = None;
+                    self.table_state.select(Some(index));
+                    return Some(Message::Rewrite);
+                }
+                Some(Message::Select(index))
+            }
+            _ => None,
+        }
     }
-    fn render_todo_window(&mut self, frame: &mut Frame, rect: Rect) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Length(3), Constraint::Min(1)])
-            .split(rect);
-        let block = Block::bordered()
-            .title(Line::from(" InputEdit ").bold().centered())
-            .title_bottom(
-                Line::from(vec![
-                    " Insert/Add <enter>".into(),
-                    " Normal <esc>".into()
-                ])
-                .centered(),
-            )
-            .border_set(border::PLAIN);
-        let width = rect.width.max(3) - 3;
-        let scroll = self.input_buffer.visual_scroll(width.into());
-        let input = Paragraph::new(self.input_buffer.value())
-            .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Insert => Style::default().fg(Color::Cyan),
-            })
-            .scroll((0, scroll as u16))
-            .block(block);
-        frame.render_widget(input, layout[0]);
-        if let InputMode::Insert = self.input_mode {
-            // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
-            frame.set_cursor_position((
-                // Put cursor past the end of the input text
-                layout[0].x + ((self.input_buffer.visual_cursor()).max(scroll) - scroll) as u16 + 1,
-                // Move one line down, from the border to the input line
-                layout[0].y + 1,
-            ))
-        };
-        let table_block = Block::bordered()
-            .title(Line::from(" TodoList ").bold().centered())
-            .title_bottom(
-                Line::from(vec![
-                    " Next <↓>".into(),
-                    " Previous <↑>".into(),
-                    " Delete <d>".into(),
-                    " Rewrite <r>".into(),
-                    " Sync <s>".into(),
-                    " Filter <w/m/o/p/g/i/u/e/n/a> ".into(),
-                ])
-                .centered(),
-            )
-            .border_set(PLAIN);
+
+    fn header_sort_key(&self, x: u16) -> Option<SortKey> {
+        let inner = Block::bordered().inner(self.table_area);
+        let widths = Column::for_width(self.table_area.width)
+            .into_iter()
+            .map(|(_, width)| width);
+        let columns = Layout::horizontal(widths).spacing(1).split(inner);
+        let column = columns
+            .iter()
+            .position(|column| column.contains(Position::new(x, column.y)))?;
+        self.table_columns.get(column).map(Column::sort_key)
+    }
+
+    //创建时间用来区分待办，同一秒内添加多个时依次往后推一秒
+    fn make_unique_id(&self, todo: &mut Todo) {
+        let todo_list = self.todo_list.read().unwrap();
+        let taken = |id: &str| todo_list.iter().any(|other| other.created_at == id);
+        todo.created_at = unique_id(&todo.created_at, taken);
+    }
+
+    //表格中的第n行对应todo_list中的visible_indices()[n]，按当前的排序方式排列
+    fn visible_indices(&self) -> Vec<usize> {
         let todo_list = self.todo_list.read().unwrap();
-        let table = todo_list
+        let mut visible = todo_list
             .iter()
             .enumerate()
-            .filter(|(_, todo)| !todo.is_hidden)
-            .map(|(index, todo)| -> Row {
-                Row::new([
-                    (index + 1).to_string(),
-                    todo.text.clone(),
-                    todo.kind.print_info(),
-                    todo.state.print_info(),
-                    todo.created_at.clone(),
-                ])
+            .filter(|(_, todo)| todo.is_visible_in(&self.current_list))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let today = chrono::Local::now().date_naive();
+        let state_rank = |index: &usize| match todo_list[*index].state {
+            TodoState::Expired => 0,
+            TodoState::InProgress => 1,
+            TodoState::UpComing => 2,
+            TodoState::NoDeadline => 3,
+        };
+        match self.sort_key {
+            SortKey::Manual => {}
+            SortKey::Text => visible.sort_by_key(|index| todo_list[*index].text.to_lowercase()),
+            SortKey::Kind => visible.sort_by_key(|index| todo_list[*index].kind.rank()),
+            SortKey::State => visible.sort_by_key(state_rank),
+            //没有下次日期的排在最后
+            SortKey::Next => visible.sort_by_key(|index| {
+                let next = todo_list[*index]
+                    .kind
+                    .next_occurrence(today, self.config.month_end);
+                (next.is_none(), next)
+            }),
+            SortKey::Created => {
+                visible.sort_by(|a, b| todo_list[*a].created_at.cmp(&todo_list[*b].created_at))
+            }
+        }
+        visible
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let row = self.table_state.selected()?;
+        self.visible_indices().get(row).copied()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        let index = self.selected_index()?;
+        Some(self.todo_list.read().unwrap()[index].created_at.clone())
+    }
+
+    fn select_id(&mut self, created_at: &str) {
+        let visible = self.visible_indices();
+        let todo_list = self.todo_list.read().unwrap();
+        let row = visible
+            .into_iter()
+            .position(|index| todo_list[index].created_at == created_at);
+        drop(todo_list);
+        match row {
+            Some(row) => self.table_state.select(Some(row)),
+            None => self.clamp_selection(),
+        }
+    }
+
+    //过滤或删除后选中的行可能超出范围
+    fn clamp_selection(&mut self) {
+        let len = self.visible_indices().len();
+        if let Some(row) = self.table_state.selected() {
+            self.table_state.select(match len {
+                0 => None,
+                len => Some(row.min(len - 1)),
+            });
+        }
+    }
+
+    //有标记时操作当前可见的标记待办，否则只操作选中的那一个
+    fn targets(&self) -> HashSet<String> {
+        if !self.marked.is_empty() {
+            let visible = self.visible_indices();
+            let todo_list = self.todo_list.read().unwrap();
+            return visible
+                .into_iter()
+                .map(|index| todo_list[index].created_at.clone())
+                .filter(|created_at| self.marked.contains(created_at))
+                .collect();
+        }
+        self.selected_id().into_iter().collect()
+    }
+
+    //批量修改只保存一份快照并保存一次
+    fn bulk_edit(&mut self, mut edit: impl FnMut(&mut Todo)) -> Option<Message> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return None;
+        }
+        self.push_undo_history();
+        self.todo_list
+            .write()
+            .unwrap()
+            .iter_mut()
+            .filter(|todo| targets.contains(&todo.created_at))
+            .for_each(&mut edit);
+        self.marked.clear();
+        Some(Message::Save)
+    }
+
+    //所有出现过的清单名称，当前清单排在最前
+    fn list_names(&self) -> Vec<String> {
+        let mut lists = vec![self.current_list.clone()];
+        for todo in self.todo_list.read().unwrap().iter() {
+            if !lists.contains(&todo.list) {
+                lists.push(todo.list.clone());
+            }
+        }
+        lists.retain(|list| !list.is_empty());
+        lists
+    }
+
+    //.json后缀导出完整数据，其他后缀导出成每行一条的输入格式
+    fn export_todo_list(&self, path: &str) -> Result<()> {
+        let todo_list = self.todo_list.read().unwrap();
+        let todo_list = todo_list
+            .iter()
+            .filter(|todo| todo.list == self.current_list)
+            .collect::<Vec<_>>();
+        if path.ends_with(".json") {
+            let file = std::fs::File::create(path).map_err(|err| Error::storage(path, err))?;
+            serde_json::to_writer_pretty(file, &todo_list).map_err(|err| Error::storage(path, err))
+        } else {
+            let lines = todo_list
+                .iter()
+                .map(|todo| todo.to_input() + "\n")
+                .collect::<String>();
+            std::fs::write(path, lines).map_err(|err| Error::storage(path, err))
+        }
+    }
+
+    //每次修改前保存一份快照，用于撤销
+    fn push_undo_history(&mut self) {
+        let todo_list = self.todo_list.read().unwrap().clone();
+        self.undo_history.push((todo_list, self.history.len()));
+        if self.undo_history.len() > 50 {
+            self.undo_history.remove(0);
+        }
+    }
+
+    fn notify(&self, level: Level, text: impl Into<String>) {
+        notice::push(&self.notices, level, text);
+    }
+
+    //输入框下方显示错误，同时记录到消息日志
+    fn input_warning(&mut self, err: String) {
+        self.notify(Level::Warn, err.clone());
+        self.input_error = Some(err);
+    }
+
+    //有新消息时暂时替换掉应用信息，同步状态始终显示在最后
+    fn render_msg_bar(&mut self, frame: &mut Frame, rect: Rect) {
+        let now = chrono::Local::now().naive_local();
+        let sync_state = self.sync_state.read().unwrap().clone();
+        let notice = notice::latest(&self.notices, now);
+        let mut spans = match notice {
+            Some(ref notice) => {
+                let color = match notice.level {
+                    Level::Info => self.theme.in_progress,
+                    Level::Warn => self.theme.alert,
+                    Level::Error => self.theme.error,
+                };
+                vec![
+                    notice.text.clone().fg(color),
+                    "  Log <L>".fg(self.theme.hint),
+                ]
+            }
+            None => vec![
+                (&self.app_info).into(),
+                " | ".into(),
+                format!(
+                    "last save at: {}",
+                    sync_state.last_save_at.format("%Y-%m-%d %H:%M:%S")
+                )
+                .into(),
+            ],
+        };
+        spans.push(" | ".into());
+        let last_sync_at = format!(
+            "last sync at: {}",
+            sync_state.last_sync_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let spinner =
+            SPINNER[now.and_utc().timestamp_subsec_millis() as usize / 100 % SPINNER.len()];
+        spans.push(match *self.sync_status.read().unwrap() {
+            SyncStatus::Idle => last_sync_at.into(),
+            SyncStatus::Discovering => {
+                format!("{} looking for server", spinner).fg(self.theme.upcoming)
+            }
+            SyncStatus::Transferring => format!("{} transferring", spinner).fg(self.theme.upcoming),
+            SyncStatus::Succeeded(ref action) => {
+                format!("{} ({})", last_sync_at, action.print_info()).into()
+            }
+            SyncStatus::Failed(ref reason) => {
+                format!("sync failed: {}", reason).fg(self.theme.error)
+            }
+        });
+        //窄屏时依次去掉应用信息和保存时间，仍然放不下时左对齐，保证消息的开头可见
+        if notice.is_none() {
+            while spans.len() > 1 && Line::from(spans.clone()).width() > rect.width as usize {
+                spans.drain(..2);
+            }
+        }
+        let msg = Line::from(spans);
+        let msg = match msg.width() > rect.width as usize {
+            true => msg.left_aligned(),
+            false => msg.centered(),
+        };
+        frame.render_widget(msg, rect);
+    }
+    fn render_todo_window(&mut self, frame: &mut Frame, rect: Rect) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Min(1),
+            ])
+            .split(rect);
+        let title = match self.input_mode {
+            InputMode::Search => " Search ",
+            InputMode::Command => " Command ",
+            _ => " InputEdit ",
+        };
+        let block = Block::bordered()
+            .title(Line::from(title).bold().centered())
+            .title_bottom(match self.input_error {
+                Some(ref err) => Line::from(format!(" {} ", err))
+                    .fg(self.theme.error)
+                    .centered(),
+                None => {
+                    let items = [" Insert/Add <enter>", " Normal <esc>", " Complete <tab> "];
+                    let width = rect.width.saturating_sub(2) as usize;
+                    let help = pack_line(&items, width).into_iter().next();
+                    Line::from(help.unwrap_or_default()).centered()
+                }
             })
-            .collect::<Table>()
+            .border_set(border::PLAIN);
+        let width = rect.width.saturating_sub(3);
+        let scroll = self.input_buffer.visual_scroll(width.into());
+        let input = Paragraph::new(self.input_buffer.value())
+            .style(match self.input_mode {
+                InputMode::Normal => Style::default(),
+                InputMode::Insert => Style::default().fg(self.theme.insert),
+                InputMode::Search => Style::default().fg(self.theme.search),
+                InputMode::Command => Style::default().fg(self.theme.command),
+            })
+            .scroll((0, scroll as u16))
+            .block(block);
+        frame.render_widget(input, layout[0]);
+        self.input_area = layout[0];
+        if self.input_mode != InputMode::Normal {
+            // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
+            frame.set_cursor_position((
+                // Put cursor past the end of the input text
+                layout[0].x + ((self.input_buffer.visual_cursor()).max(scroll) - scroll) as u16 + 1,
+                // Move one line down, from the border to the input line
+                layout[0].y + 1,
+            ))
+        };
+        self.render_input_preview(frame, layout[1]);
+        match self.view {
+            View::Table => self.render_table(frame, layout[2]),
+            View::Calendar => self.render_calendar(frame, layout[2]),
+            View::Agenda => self.render_agenda(frame, layout[2]),
+            View::Stats => self.render_stats(frame, layout[2]),
+        }
+        if let Some(ref completion) = self.completion {
+            let x = layout[0].x + (completion.start.max(scroll) - scroll) as u16 + 1;
+            Self::render_completion(frame, completion, x, layout[0].bottom() - 1);
+        }
+        if let InputMode::Command = self.input_mode {
+            self.render_palette(frame, layout[0]);
+        }
+    }
+    fn render_calendar(&self, frame: &mut Frame, rect: Rect) {
+        //窄屏时当天的待办放到月历下方
+        let weeks = calendar::month_grid(self.calendar_date).len() as u16;
+        let layout = match rect.width >= 7 * 5 + 2 + 24 {
+            true => Layout::horizontal([Constraint::Length(7 * 5 + 2), Constraint::Min(0)]),
+            false => Layout::vertical([Constraint::Length(weeks * 2 + 4), Constraint::Min(0)]),
+        }
+        .split(rect);
+        let todo_list = self.todo_list.read().unwrap();
+        let today = chrono::Local::now().date_naive();
+        let grid = calendar::month_grid(self.calendar_date)
+            .into_iter()
+            .map(|week| {
+                Row::new(week.map(|date| {
+                    let Some(date) = date else {
+                        return Cell::default();
+                    };
+                    let items = calendar::items_on(
+                        &todo_list,
+                        date,
+                        &self.current_list,
+                        self.config.month_end,
+                    );
+                    let mut style = match items.first() {
+                        Some(todo) => self.theme.kind(&todo.kind).bold(),
+                        None => Style::new(),
+                    };
+                    if date == today {
+                        style = style.underlined();
+                    }
+                    if date == self.calendar_date {
+                        style = style.reversed();
+                    }
+                    let mark = match items.len() {
+                        0 => "  ".to_string(),
+                        1..=9 => format!("·{}", items.len()),
+                        _ => "·+".to_string(),
+                    };
+                    Cell::from(format!("{:>2}{}", date.day(), mark)).style(style)
+                }))
+                .bottom_margin(1)
+            })
+            .collect::<Vec<_>>();
+        let grid = Table::new(grid, [Constraint::Length(4); 7])
             .header(
-                Row::new(["Index", "Content", "Kind", "State", "CreatedAt"])
-                    .style(Style::new().bold().underlined())
-                    .top_margin(1)
+                Row::new(["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"])
+                    .style(Style::new().bold())
                     .bottom_margin(1),
             )
-            .footer(
-                Row::new([
-                    format!("Total: {}", todo_list.len()),
-                    format!(
-                        "Filtered: {}",
-                        todo_list.iter().filter(|todo| !todo.is_hidden).count()
-                    ),
-                ])
-                .top_margin(1),
+            .block(
+                Block::bordered()
+                    .title(
+                        Line::from(format!(" {} ", self.calendar_date.format("%B %Y")))
+                            .bold()
+                            .centered(),
+                    )
+                    .title_bottom(Line::from(" Month <[/]> Back <esc> ").centered())
+                    .border_set(PLAIN),
+            );
+        frame.render_widget(grid, layout[0]);
+        let items = calendar::items_on(
+            &todo_list,
+            self.calendar_date,
+            &self.current_list,
+            self.config.month_end,
+        )
+        .into_iter()
+        .map(|todo| {
+            Line::from(vec![
+                Span::styled(todo.state.print_info(), self.theme.state(&todo.state)),
+                "  ".into(),
+                todo.text.clone().into(),
+                "  ".into(),
+                Span::styled(todo.kind.print_info(), self.theme.kind(&todo.kind)),
+            ])
+        })
+        .collect::<List>()
+        .block(
+            Block::bordered()
+                .title(
+                    Line::from(format!(" {} ", self.calendar_date.format("%Y-%m-%d %a")))
+                        .bold()
+                        .centered(),
+                )
+                .title_bottom(Line::from(" Move <←↓↑→> Filter <enter> ").centered())
+                .border_set(PLAIN),
+        );
+        frame.render_widget(items, layout[1]);
+    }
+    fn render_agenda(&self, frame: &mut Frame, rect: Rect) {
+        let todo_list = self.todo_list.read().unwrap();
+        let today = chrono::Local::now().date_naive();
+        let agenda = Agenda::new(
+            &todo_list,
+            today,
+            self.config.agenda.days,
+            &self.current_list,
+            self.config.month_end,
+        );
+        let mut lines = vec![];
+        let mut section = |title: String, color, items: &[&Todo]| {
+            lines.push(Line::from(title).bold().fg(color));
+            for todo in items {
+                lines.push(Line::from(vec![
+                    "  ".into(),
+                    Span::styled(agenda::item_line(todo), self.theme.kind(&todo.kind)),
+                ]));
+            }
+            lines.push(Line::default());
+        };
+        if !agenda.expired.is_empty() {
+            section("Expired".to_string(), self.theme.expired, &agenda.expired);
+        }
+        for (date, items) in agenda.days.iter() {
+            let color = if *date == today {
+                self.theme.in_progress
+            } else {
+                self.theme.upcoming
+            };
+            section(agenda::day_title(*date, today), color, items);
+        }
+        if !agenda.no_deadline.is_empty() {
+            section(
+                "No deadline".to_string(),
+                self.theme.no_deadline,
+                &agenda.no_deadline,
+            );
+        }
+        let max_scroll = (lines.len() as u16).saturating_sub(rect.height.saturating_sub(2));
+        let agenda = Paragraph::new(lines)
+            .scroll((self.agenda_scroll.min(max_scroll), 0))
+            .block(
+                Block::bordered()
+                    .title(
+                        Line::from(format!(" Agenda, next {} days ", self.config.agenda.days))
+                            .bold()
+                            .centered(),
+                    )
+                    .title_bottom(Line::from(" Scroll <↑/↓> Back <esc> ").centered())
+                    .border_set(PLAIN),
+            );
+        frame.render_widget(agenda, rect);
+    }
+    fn render_stats(&self, frame: &mut Frame, rect: Rect) {
+        let todo_list = self.todo_list.read().unwrap();
+        let todo_list = todo_list
+            .iter()
+            .filter(|todo| todo.list == self.current_list)
+            .collect::<Vec<_>>();
+        let today = chrono::Local::now().date_naive();
+        let block = |title: &str| {
+            Block::bordered()
+                .title(Line::from(format!(" {} ", title)).bold().centered())
+                .border_set(PLAIN)
+        };
+        let rows = Layout::vertical([
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Min(3),
+        ])
+        .split(rect);
+        let top = Layout::horizontal([Constraint::Percentage(50); 2]).split(rows[0]);
+        let middle = Layout::horizontal([Constraint::Percentage(50); 2]).split(rows[1]);
+
+        let bar = |label: String, value: u64, style: Style| {
+            Bar::default()
+                .label(label.into())
+                .value(value)
+                .style(style)
+                .value_style(style.reversed())
+        };
+        let kinds = stats::count_by_kind(&todo_list)
+            .into_iter()
+            .map(|(name, count)| {
+                let style = self.theme.kind_named(name);
+                bar(name.to_string(), count, style)
+            })
+            .collect::<Vec<_>>();
+        let kinds = BarChart::default()
+            .data(BarGroup::default().bars(&kinds))
+            .bar_width(8)
+            .bar_gap(1)
+            .block(block("By kind"));
+        frame.render_widget(kinds, top[0]);
+        let states = stats::count_by_state(&todo_list)
+            .into_iter()
+            .map(|(state, count)| bar(state.print_info(), count, self.theme.state(&state)))
+            .collect::<Vec<_>>();
+        let states = BarChart::default()
+            .data(BarGroup::default().bars(&states))
+            .bar_width(10)
+            .bar_gap(1)
+            .block(block("By state"));
+        frame.render_widget(states, top[1]);
+
+        let per_day = stats::done_per_day(&self.history, today, 30);
+        let done = per_day.iter().sum::<u64>();
+        let per_day = Sparkline::default()
+            .data(&per_day)
+            .style(Style::new().fg(self.theme.in_progress))
+            .block(block(&format!("Done in the last 30 days: {}", done)));
+        frame.render_widget(per_day, middle[0]);
+        let per_week = stats::done_per_week(&self.history, today, 8)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (done, late))| {
+                BarGroup::default()
+                    .label(Line::from(format!("-{}w", 7 - index)).centered())
+                    .bars(&[
+                        bar(String::new(), done, Style::new().fg(self.theme.in_progress)),
+                        bar(String::new(), late, Style::new().fg(self.theme.expired)),
+                    ])
+            })
+            .collect::<Vec<_>>();
+        let mut chart = BarChart::default()
+            .bar_width(2)
+            .group_gap(1)
+            .block(block("Done / late per week"));
+        for group in per_week.iter() {
+            chart = chart.data(group.clone());
+        }
+        frame.render_widget(chart, middle[1]);
+
+        let expired = todo_list
+            .iter()
+            .filter(|todo| todo.state == TodoState::Expired)
+            .count();
+        let average = stats::average_time_to_complete(&self.history)
+            .map(stats::format_duration)
+            .unwrap_or("-".to_string());
+        let mut lines = vec![
+            Line::from(vec![
+                "Average time to complete: ".fg(self.theme.hint),
+                average.into(),
+                "   Overdue now: ".fg(self.theme.hint),
+                Span::styled(expired.to_string(), Style::new().fg(self.theme.expired)),
+            ]),
+            Line::from("Streaks:".fg(self.theme.hint)),
+        ];
+        for todo in todo_list.iter() {
+            if !matches!(
+                todo.kind,
+                TodoKind::Week(_) | TodoKind::Month(_) | TodoKind::Recur(_)
+            ) {
+                continue;
+            }
+            lines.push(Line::from(vec![
+                format!(
+                    "  {:>3}  ",
+                    stats::streak(todo, &self.history, today, self.config.month_end)
+                )
+                .bold(),
+                todo.text.clone().into(),
+                "  ".into(),
+                Span::styled(todo.kind.print_info(), self.theme.kind(&todo.kind)),
+            ]));
+        }
+        let summary = Paragraph::new(lines)
+            .block(block("Summary").title_bottom(Line::from(" Done <x> Back <esc> ").centered()));
+        frame.render_widget(summary, rows[2]);
+    }
+    fn render_table(&mut self, frame: &mut Frame, rect: Rect) {
+        let columns = Column::for_width(rect.width);
+        let compact = columns.len() == 1;
+        let items = self
+            .keymap
+            .help()
+            .into_iter()
+            .map(|(label, keys)| format!(" {} <{}>", label, keys))
+            .collect::<Vec<_>>();
+        //帮助栏放不下时换行显示在表格下方，高度也不够时只显示第一行
+        let mut help = pack_line(&items, rect.width.saturating_sub(2) as usize);
+        let (rect, help_area) = match help.len() {
+            0 | 1 => (rect, None),
+            len if rect.height >= len as u16 + 10 => {
+                let layout =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(len as u16 - 1)])
+                        .split(rect);
+                (layout[0], Some(layout[1]))
+            }
+            _ => {
+                let more = " More <?> ".to_string();
+                let width = rect.width.saturating_sub(2 + more.len() as u16) as usize;
+                let first = pack_line(&items, width).into_iter().next();
+                help = vec![first.unwrap_or_default() + &more];
+                (rect, None)
+            }
+        };
+        self.table_area = rect;
+        self.table_columns = columns.iter().map(|(column, _)| *column).collect();
+        let visible = self.visible_indices();
+        let todo_list = self.todo_list.read().unwrap();
+        let count = match rect.width {
+            0..80 => match self.marked.len() {
+                0 => format!(" {}/{} ", visible.len(), todo_list.len()),
+                marked => format!(" {}/{} *{} ", visible.len(), todo_list.len(), marked),
+            },
+            _ => format!(
+                " Total: {} Filtered: {} Marked: {} ",
+                todo_list.len(),
+                visible.len(),
+                self.marked.len()
+            ),
+        };
+        let table_block = Block::bordered()
+            .title(
+                Line::from(match self.current_list.as_str() {
+                    "" => " TodoList ".to_string(),
+                    list => format!(" TodoList [{}] ", list),
+                })
+                .bold()
+                .centered(),
             )
+            .title(Line::from(count).right_aligned())
+            .title_bottom(Line::from(help.first().cloned().unwrap_or_default()).centered())
+            .border_set(PLAIN);
+        let today = chrono::Local::now().date_naive();
+        let rows = visible
+            .iter()
+            .map(|index| &todo_list[*index])
+            .enumerate()
+            .map(|(index, todo)| -> Row {
+                let marked = self.marked.contains(&todo.created_at);
+                //没有序号列时标记显示在内容前面
+                let text = match marked && !self.table_columns.contains(&Column::Index) {
+                    true => format!("* {}", todo.text),
+                    false => todo.text.clone(),
+                };
+                let kind = Span::styled(todo.kind.print_info(), self.theme.kind(&todo.kind));
+                Row::new(self.table_columns.iter().map(|column| {
+                    match column {
+                        Column::Index => Cell::from(match marked {
+                            true => format!("*{}", index + 1),
+                            false => (index + 1).to_string(),
+                        }),
+                        Column::Content if compact => Cell::from(Line::from(vec![
+                            text.clone().into(),
+                            "  ".into(),
+                            kind.clone(),
+                        ])),
+                        Column::Content => Cell::from(text.clone()),
+                        Column::Kind => Cell::from(kind.clone()),
+                        Column::State => Cell::from(todo.state.print_info()),
+                        Column::Next => Cell::from(
+                            todo.kind
+                                .next_occurrence(today, self.config.month_end)
+                                .map(|date| date.to_string())
+                                .unwrap_or_default(),
+                        ),
+                        Column::CreatedAt => Cell::from(todo.created_at.clone()),
+                    }
+                }))
+                .style(self.theme.state(&todo.state))
+            })
+            .collect::<Vec<_>>();
+        let mut table = Table::new(rows, columns.iter().map(|(_, width)| *width))
             .row_highlight_style(Style::new().reversed())
-            .widths([
-                Constraint::Percentage(10),
-                Constraint::Percentage(40),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
-                Constraint::Percentage(20),
-            ])
             .block(table_block); //TODO 文本多行显示
-        frame.render_stateful_widget(table, layout[1], &mut self.table_state);
+        if !compact {
+            table = table.header(
+                Row::new(self.table_columns.iter().map(Column::title))
+                    .style(Style::new().bold().underlined())
+                    .top_margin(1)
+                    .bottom_margin(1),
+            );
+        }
+        frame.render_stateful_widget(table, rect, &mut self.table_state);
+        if let Some(help_area) = help_area {
+            let lines = help.into_iter().skip(1).map(Line::from).collect::<Vec<_>>();
+            frame.render_widget(Paragraph::new(lines).centered(), help_area);
+        }
     }
-    fn save_todo_list(&mut self) {
+    //输入时实时显示解析结果，解析失败的后缀标红
+    fn render_input_preview(&self, frame: &mut Frame, rect: Rect) {
+        let input = self.input_buffer.value();
+        let preview = if self.input_mode != InputMode::Insert || input.trim().is_empty() {
+            //放不下时只显示能放下的几项
+            let items = [
+                " Deadline <text -mon/15/2025-03-01 14:30/tomorrow/every 2 weeks>",
+                " Progress <text @3/10>",
+                " Literal <\\- \\@> ",
+            ];
+            let short = [" Deadline <text -date>", " Progress <text @3/10> "];
+            let width = rect.width as usize;
+            let line = match pack_line(&items, width).into_iter().next() {
+                Some(line) if line.chars().count() <= width => line,
+                _ => pack_line(&short, width)
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default(),
+            };
+            Line::from(line).fg(self.theme.hint)
+        } else {
+            match Todo::new(input) {
+                Ok(mut todo) => {
+                    todo.state_check(self.config.month_end);
+                    Line::from(vec![
+                        " Text: ".fg(self.theme.hint),
+                        todo.text.into(),
+                        "  Kind: ".fg(self.theme.hint),
+                        Span::styled(todo.kind.print_info(), self.theme.kind(&todo.kind)),
+                        "  State: ".fg(self.theme.hint),
+                        Span::styled(todo.state.print_info(), self.theme.state(&todo.state)),
+                    ])
+                }
+                Err(_) => {
+                    let (text, suffix) = Todo::split_input(input);
+                    let suffix = suffix.map(|(_, suffix)| suffix).unwrap_or_default();
+                    Line::from(vec![
+                        " Text: ".fg(self.theme.hint),
+                        text.into(),
+                        "  Unknown: ".fg(self.theme.hint),
+                        format!("-{}", suffix).fg(self.theme.error).underlined(),
+                    ])
+                }
+            }
+        };
+        frame.render_widget(preview.centered(), rect);
+    }
+    fn render_palette(&self, frame: &mut Frame, input_area: Rect) {
+        let suggestions = palette::suggestions(self.input_buffer.value(), &self.list_names());
+        if suggestions.is_empty() {
+            return;
+        }
+        let height = suggestions.len().min(10) as u16 + 2;
+        let area = Rect::new(
+            input_area.x,
+            input_area.bottom() - 1,
+            input_area.width,
+            height,
+        )
+        .intersection(frame.area());
+        let list = List::new(suggestions.into_iter().map(|(_, description)| description))
+            .highlight_style(Style::new().reversed())
+            .block(Block::bordered().border_set(PLAIN));
+        let mut list_state = ListState::default().with_selected(Some(self.palette_selected));
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut list_state);
+    }
+    fn render_completion(frame: &mut Frame, completion: &Completion, x: u16, y: u16) {
+        let width = completion
+            .candidates
+            .iter()
+            .map(|candidate| candidate.chars().count())
+            .max()
+            .unwrap_or_default() as u16
+            + 2;
+        let height = completion.candidates.len().min(8) as u16 + 2;
+        let area = Rect::new(x, y, width, height).intersection(frame.area());
+        let list = List::new(completion.candidates.iter().map(String::as_str))
+            .highlight_style(Style::new().reversed())
+            .block(Block::bordered().border_set(PLAIN));
+        let mut list_state = ListState::default().with_selected(Some(completion.selected));
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut list_state);
+    }
+    //帮助页面根据当前的按键绑定和输入语法示例生成
+    fn help_lines(&self) -> Vec<Line<'static>> {
+        let section = |title: &str| Line::from(title.to_string()).bold().underlined();
+        let entry = |label: &str, value: &str| {
+            Line::from(vec![
+                format!("  {:<12}", label).fg(self.theme.hint),
+                value.to_string().into(),
+            ])
+        };
+        let mut lines = vec![section("Normal mode")];
+        for (label, keys) in self.keymap.help() {
+            lines.push(entry(&label, &keys));
+        }
+        lines.push(Line::default());
+        lines.push(section("Insert mode"));
+        lines.push(entry("Add", "enter"));
+        lines.push(entry("Normal", "esc"));
+        lines.push(entry("Complete", "tab / shift+tab, enter to accept"));
+        lines.push(Line::default());
+        lines.push(section("Search mode"));
+        lines.push(entry("Filter", "type to filter by content"));
+        lines.push(entry("Keep", "enter"));
+        lines.push(entry("Cancel", "esc"));
+        lines.push(Line::default());
+        lines.push(section("Mouse"));
+        lines.push(entry("Select", "click a row, wheel to move"));
+        lines.push(entry("Rewrite", "double click a row"));
+        lines.push(entry(
+            "Sort",
+            "click a column header, again for manual order",
+        ));
+        lines.push(entry("Insert", "click the input box"));
+        lines.push(Line::default());
+        lines.push(section("Calendar"));
+        lines.push(entry("Day", "←/→ or h/l"));
+        lines.push(entry("Week", "↑/↓ or k/j"));
+        lines.push(entry("Month", "[/] or pageup/pagedown"));
+        lines.push(entry("Filter", "enter, show the day in the list"));
+        lines.push(entry("Back", "esc"));
+        lines.push(Line::default());
+        lines.push(section("Agenda"));
+        lines.push(entry("Scroll", "↑/↓ or k/j, pageup/pagedown"));
+        lines.push(entry("Back", "esc"));
+        lines.push(entry("Terminal", "ttd-v2 agenda [days]"));
+        lines.push(Line::default());
+        lines.push(section("Input syntax"));
+        lines.push(entry("Deadline", "<text> -<date or rule>"));
+        lines.push(entry("Progress", "<text> @<progress>"));
+        lines.push(entry("Literal", "\\- and \\@ keep - and @ in the text"));
+        lines.push(Line::default());
+        lines.push(section("Examples"));
+        for (kind, example) in SYNTAX_EXAMPLES {
+            lines.push(entry(kind, example));
+        }
+        lines
+    }
+    fn render_help(&self, frame: &mut Frame, scroll: u16) {
+        let lines = self.help_lines();
+        let area = Self::popup_area(frame.area(), 70, frame.area().height * 4 / 5);
+        let max_scroll = (lines.len() as u16).saturating_sub(area.height.saturating_sub(2));
+        let help = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll.min(max_scroll), 0))
+            .block(
+                Block::bordered()
+                    .title(Line::from(" Help ").bold().centered())
+                    .title_bottom(Line::from(" Scroll <j/k/↑/↓> Close <esc/?> ").centered())
+                    .border_set(PLAIN),
+            );
+        frame.render_widget(Clear, area);
+        frame.render_widget(help, area);
+    }
+    //最新的消息在最上面
+    fn render_log(&self, frame: &mut Frame, scroll: u16) {
+        let lines = self
+            .notices
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .map(|notice| {
+                let color = match notice.level {
+                    Level::Info => self.theme.hint,
+                    Level::Warn => self.theme.alert,
+                    Level::Error => self.theme.error,
+                };
+                Line::from(vec![
+                    format!("{} ", notice.at.format("%m-%d %H:%M:%S")).fg(self.theme.hint),
+                    format!("{:<6}", notice.level.print_info()).fg(color),
+                    notice.text.clone().into(),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let area = Self::popup_area(frame.area(), 70, frame.area().height * 4 / 5);
+        let max_scroll = (lines.len() as u16).saturating_sub(area.height.saturating_sub(2));
+        let log = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll.min(max_scroll), 0))
+            .block(
+                Block::bordered()
+                    .title(Line::from(" Messages ").bold().centered())
+                    .title_bottom(Line::from(" Scroll <j/k/↑/↓> Close <esc/L> ").centered())
+                    .border_set(PLAIN),
+            );
+        frame.render_widget(Clear, area);
+        frame.render_widget(log, area);
+    }
+    fn render_alert(&self, frame: &mut Frame, alert: &str) {
+        let title = match self.alerts.len() {
+            0 | 1 => " Reminder ".to_string(),
+            len => format!(" Reminder (1/{}) ", len),
+        };
+        let area = Self::popup_area(frame.area(), 50, 5);
+        let popup = Paragraph::new(alert)
+            .wrap(Wrap { trim: true })
+            .centered()
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).bold().centered())
+                    .title_bottom(Line::from(" Dismiss <any key> ").centered())
+                    .border_set(PLAIN)
+                    .fg(self.theme.alert),
+            );
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+    fn popup_area(rect: Rect, percent_x: u16, height: u16) -> Rect {
+        //窄屏时弹窗占满整个宽度
+        let percent_x = if rect.width < 60 { 100 } else { percent_x };
+        let vertical = Layout::vertical([Constraint::Length(height)])
+            .flex(ratatui::layout::Flex::Center)
+            .split(rect);
+        let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)])
+            .flex(ratatui::layout::Flex::Center)
+            .split(vertical[0]);
+        horizontal[0]
+    }
+    fn save_todo_list(&mut self) -> Result<()> {
         {
             self.todo_list
                 .write()
@@ -468,41 +2013,181 @@ impl App {
                 .iter_mut()
                 .for_each(Todo::reset_hidden_flag);
         }
-        let todo_list_file = std::fs::File::create(TODO_LIST_PATH.as_path()).unwrap();
-        let sync_state_file = std::fs::File::create(SYNC_STATE_PATH.as_path()).unwrap();
-        serde_json::to_writer(todo_list_file, &self.todo_list.read().unwrap().clone()).unwrap();
-        serde_json::to_writer(sync_state_file, &self.sync_state.read().unwrap().clone()).unwrap();
+        let todo_list = self.todo_list.read().unwrap().clone();
+        let sync_state = self.sync_state.read().unwrap().clone();
+        Self::write_json(&self.data_path(&TODO_LIST_PATH), &todo_list)?;
+        Self::write_json(&self.data_path(&SYNC_STATE_PATH), &sync_state)
+    }
+    fn write_json(path: &Path, value: &impl serde::Serialize) -> Result<()> {
+        let file = std::fs::File::create(path).map_err(|err| Error::storage(path, err))?;
+        serde_json::to_writer(file, value).map_err(|err| Error::storage(path, err))
+    }
+    //文件不存在或者为空时返回None
+    pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+        match std::fs::read(path) {
+            Ok(raw) if raw.is_empty() => Ok(None),
+            Ok(raw) => serde_json::from_slice(&raw)
+                .map(Some)
+                .map_err(|err| Error::storage(path, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::storage(path, err)),
+        }
+    }
+    //写入失败时不退出，记录到消息日志中
+    fn report(&self, result: Result<()>) {
+        if let Err(err) = result {
+            self.notify(Level::Error, format!("Save failed: {}", err));
+        }
     }
-    fn load_todo_list(&mut self) {
-        let todo_list_file = std::fs::read(TODO_LIST_PATH.as_path()).unwrap();
-        if !todo_list_file.is_empty() {
-            *self.todo_list.write().unwrap() = serde_json::from_slice(&todo_list_file).unwrap();
+    fn data_path(&self, path: &LazyLock<PathBuf>) -> PathBuf {
+        match (&self.data_dir, path.file_name()) {
+            (Some(dir), Some(file_name)) => dir.join(file_name),
+            _ => path.to_path_buf(),
+        }
+    }
+    fn save_history(&self) -> Result<()> {
+        Self::write_json(&self.data_path(&HISTORY_PATH), &self.history)
+    }
+    //配置有误时不退出，提示后使用默认配置
+    fn load_config(&self, path: &Path) -> Config {
+        Config::load(path).unwrap_or_else(|err| {
+            self.notify(Level::Warn, format!("Ignored config: {}", err));
+            Config::default()
+        })
+    }
+    fn load_todo_list(&mut self) -> Result<()> {
+        let path = self.data_path(&TODO_LIST_PATH);
+        if let Some(mut todo_list) = Self::read_json::<Vec<Todo>>(&path)? {
+            dedup_ids(&mut todo_list);
+            *self.todo_list.write().unwrap() = todo_list;
         }
         self.todo_list
             .write()
             .unwrap()
             .iter_mut()
-            .for_each(Todo::state_check);
-        let sync_state_file = std::fs::read(SYNC_STATE_PATH.as_path()).unwrap();
-        if !sync_state_file.is_empty() {
-            *self.sync_state.write().unwrap() = serde_json::from_slice(&sync_state_file).unwrap();
+            .for_each(|todo| todo.state_check(self.config.month_end));
+        //完成记录只用于统计，损坏时忽略
+        match Self::read_json(&self.data_path(&HISTORY_PATH)) {
+            Ok(history) => self.history = history.unwrap_or_default(),
+            Err(err) => self.notify(Level::Warn, format!("Ignored history: {}", err)),
+        }
+        if let Some(sync_state) = Self::read_json(&self.data_path(&SYNC_STATE_PATH))? {
+            *self.sync_state.write().unwrap() = sync_state;
         }
+        Ok(())
     }
 
     fn sync_data(&mut self) {
-        let sync_state = Arc::clone(&self.sync_state);
-        let todo_list = Arc::clone(&self.todo_list);
-        let local_sync_state = sync_state.read().unwrap().clone();
-        let local_todo_list = todo_list.read().unwrap().clone();
-        std::thread::spawn(
-            move || match crate::sync_app_data(local_sync_state, local_todo_list) {
-                Ok(Some((server_sync_state, server_todo_list))) => {
-                    *sync_state.write().unwrap() = server_sync_state;
-                    *todo_list.write().unwrap() = server_todo_list;
+        if !self.start_sync() {
+            self.notify(Level::Warn, "Sync already in progress");
+        }
+    }
+
+    //同一时间只允许一次同步，已经在同步时返回false
+    fn start_sync(&mut self) -> bool {
+        {
+            let mut sync_status = self.sync_status.write().unwrap();
+            if sync_status.is_running() {
+                return false;
+            }
+            *sync_status = SyncStatus::Discovering;
+        }
+        self.last_sync = Some(Instant::now());
+        self.changed_at = None;
+        self.resync_pending = false;
+        let sync_result = Arc::clone(&self.sync_result);
+        let notices = Arc::clone(&self.notices);
+        let sync_status = Arc::clone(&self.sync_status);
+        let local_sync_state = self.sync_state.read().unwrap().clone();
+        let local_todo_list = self.todo_list.read().unwrap().clone();
+        notice::push(&notices, Level::Info, "Sync started");
+        std::thread::spawn(move || {
+            let progress = |status| *sync_status.write().unwrap() = status;
+            let status = match crate::sync_app_data(local_sync_state, local_todo_list, progress) {
+                //成功时保持传输中的状态，直到主循环应用了结果
+                Ok(Some(result)) => {
+                    *sync_result.write().unwrap() = Some(result);
+                    return;
                 }
-                _ => {}
-            },
-        );
+                Ok(None) => {
+                    notice::push(&notices, Level::Warn, "No server found");
+                    SyncStatus::Failed("no server found".to_string())
+                }
+                Err(err) => {
+                    notice::push(&notices, Level::Error, format!("Sync failed: {}", err));
+                    SyncStatus::Failed(err.to_string())
+                }
+            };
+            *sync_status.write().unwrap() = status;
+        });
+        true
+    }
+
+    fn poll_sync(&mut self, now: Instant) {
+        let result = self.sync_result.write().unwrap().take();
+        if let Some((action, server_sync_state, server_todo_list)) = result {
+            self.apply_sync(action, server_sync_state, server_todo_list);
+        }
+        let running = self.sync_status.read().unwrap().is_running();
+        if self.quitting && !running {
+            self.exit = true;
+        } else if !running && self.sync_due(now) {
+            self.start_sync();
+        }
+    }
+
+    //定时同步从上次开始同步算起，修改后的同步等到一段时间内没有新的修改
+    //同步期间的修改不受这些设置影响，总是会再同步一次
+    fn sync_due(&self, now: Instant) -> bool {
+        let elapsed = |at: Instant, secs: u64| {
+            secs > 0 && now.saturating_duration_since(at) >= Duration::from_secs(secs)
+        };
+        self.resync_pending
+            || self
+                .changed_at
+                .is_some_and(|at| elapsed(at, self.config.sync.debounce_seconds))
+            || self
+                .last_sync
+                .is_some_and(|at| elapsed(at, self.config.sync.interval_minutes * 60))
+    }
+
+    //同步期间本地有修改时不使用下载的数据，之后再同步一次上传本地的修改
+    fn apply_sync(
+        &mut self,
+        action: SyncAction,
+        server_sync_state: SyncState,
+        mut server_todo_list: Vec<Todo>,
+    ) {
+        let len = server_todo_list.len();
+        let text = match action {
+            SyncAction::Init => format!("Initialized server with {} items", len),
+            SyncAction::NoChange => "Already up to date".to_string(),
+            SyncAction::Upload => format!("Uploaded {} items to server", len),
+            SyncAction::Download => format!("Downloaded {} items from server", len),
+        };
+        self.resync_pending = self.changed_at.is_some();
+        if action != SyncAction::Download {
+            self.sync_state.write().unwrap().last_sync_at = server_sync_state.last_sync_at;
+        } else if self.changed_at.is_some() {
+            self.notify(Level::Warn, "Kept local changes made during sync");
+            *self.sync_status.write().unwrap() =
+                SyncStatus::Failed("changed during sync".to_string());
+            return;
+        } else {
+            let selected = self.selected_id();
+            self.push_undo_history();
+            dedup_ids(&mut server_todo_list);
+            *self.todo_list.write().unwrap() = server_todo_list;
+            *self.sync_state.write().unwrap() = server_sync_state;
+            let result = self.save_todo_list();
+            self.report(result);
+            match selected {
+                Some(created_at) => self.select_id(&created_at),
+                None => self.clamp_selection(),
+            }
+        }
+        self.notify(Level::Info, text);
+        *self.sync_status.write().unwrap() = SyncStatus::Succeeded(action);
     }
 
     fn get_app_info() -> String {
@@ -511,3 +2196,878 @@ impl App {
         format!("{} v{}", name, version)
     }
 }
+
+//已经被占用时依次往后推一秒，无法解析的创建时间原样返回
+fn unique_id(created_at: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut id = created_at.to_string();
+    while taken(&id) {
+        let Ok(time) = NaiveDateTime::parse_from_str(&id, "%Y-%m-%d %H:%M:%S") else {
+            break;
+        };
+        id = (time + Duration::from_secs(1))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+    }
+    id
+}
+
+//旧数据和同步下来的列表中可能有创建时间相同的待办，读取后重新编号
+fn dedup_ids(todo_list: &mut [Todo]) {
+    let mut seen = HashSet::new();
+    for todo in todo_list.iter_mut() {
+        todo.created_at = unique_id(&todo.created_at, |id| seen.contains(id));
+        seen.insert(todo.created_at.clone());
+    }
+}
+
+//按顺序把每一项放进宽度为width的行里，放不下时换到下一行
+fn pack_line(items: &[impl AsRef<str>], width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for item in items {
+        let item = item.as_ref();
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + item.chars().count() <= width => {
+                line.push_str(item)
+            }
+            _ => lines.push(item.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::SyncConfig, events::ScriptedEvents, todo::MonthEnd};
+    use ratatui::backend::TestBackend;
+    use std::ops::{Deref, DerefMut};
+
+    //测试结束时删除临时目录
+    struct TestApp {
+        app: App,
+        temp_dir: PathBuf,
+    }
+
+    impl Deref for TestApp {
+        type Target = App;
+
+        fn deref(&self) -> &App {
+            &self.app
+        }
+    }
+
+    impl DerefMut for TestApp {
+        fn deref_mut(&mut self) -> &mut App {
+            &mut self.app
+        }
+    }
+
+    impl Drop for TestApp {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.temp_dir);
+        }
+    }
+
+    //每个测试使用单独的临时目录，不会写入真实数据
+    fn test_app(name: &str, inputs: &[&str]) -> TestApp {
+        let data_dir =
+            std::env::temp_dir().join(format!("ttd-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let todo_list = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let mut todo = Todo::new(input).unwrap();
+                todo.created_at = format!("2025-01-01 00:00:{:02}", index);
+                todo
+            })
+            .collect();
+        let app = App {
+            todo_list: Arc::new(RwLock::new(todo_list)),
+            data_dir: Some(data_dir.clone()),
+            ..Default::default()
+        };
+        TestApp {
+            app,
+            temp_dir: data_dir,
+        }
+    }
+
+    //同步会访问网络，测试中只在已经有同步进行时才处理
+    fn send(app: &mut App, msg: Message) {
+        let mut msg = Some(msg);
+        while let Some(current) = msg {
+            if matches!(current, Message::Sync) && !app.sync_status.read().unwrap().is_running() {
+                break;
+            }
+            msg = app.update(current);
+        }
+    }
+
+    //每个按键产生的消息处理完后再读取下一个按键
+    fn press(app: &mut App, script: &str) {
+        let mut events = ScriptedEvents::new(script);
+        while !events.is_empty() {
+            if let Some(msg) = app.handle_events(&mut events).unwrap() {
+                send(app, msg);
+            }
+        }
+    }
+
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| app.view(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(width as usize)
+            .map(|row| {
+                let line = row.iter().map(|cell| cell.symbol()).collect::<String>();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn screen(app: &mut App) -> String {
+        render(app, 120, 30).join("\n")
+    }
+
+    fn texts(app: &App) -> Vec<String> {
+        let todo_list = app.todo_list.read().unwrap();
+        todo_list.iter().map(|todo| todo.text.clone()).collect()
+    }
+
+    //表格中显示的顺序
+    fn rows(app: &App) -> Vec<String> {
+        let visible = app.visible_indices();
+        let todo_list = app.todo_list.read().unwrap();
+        visible
+            .into_iter()
+            .map(|index| todo_list[index].text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn filtered_delete_test() {
+        let mut app = test_app("delete", &["a -fri", "b", "c -mon", "d"]);
+        send(&mut app, Message::Filter(FilterType::Week));
+        send(&mut app, Message::SelectLast);
+        assert_eq!(app.table_state.selected(), Some(1));
+        send(&mut app, Message::Delete);
+        assert_eq!(texts(&app), ["a", "b", "d"]);
+        //保存后过滤被清除，选中的行不会超出范围
+        assert_eq!(app.table_state.selected(), Some(1));
+        send(&mut app, Message::Undo);
+        assert_eq!(texts(&app), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn filtered_navigation_test() {
+        let mut app = test_app("navigation", &["a -fri", "b", "c -mon", "d"]);
+        send(&mut app, Message::Filter(FilterType::Week));
+        send(&mut app, Message::SelectNext);
+        assert_eq!(app.table_state.selected(), Some(1));
+        send(&mut app, Message::SelectNext);
+        assert_eq!(app.table_state.selected(), Some(0));
+        send(&mut app, Message::SelectPrevious);
+        assert_eq!(app.table_state.selected(), Some(1));
+        send(&mut app, Message::Select(2));
+        assert_eq!(app.table_state.selected(), Some(1));
+        //过滤后只剩一项时选中的行回到范围内
+        send(
+            &mut app,
+            Message::Filter(FilterType::Search("a".to_string())),
+        );
+        assert_eq!(app.table_state.selected(), Some(0));
+        send(
+            &mut app,
+            Message::Filter(FilterType::Search("zzz".to_string())),
+        );
+        assert_eq!(app.table_state.selected(), None);
+    }
+
+    #[test]
+    fn filtered_rewrite_test() {
+        let mut app = test_app("rewrite", &["a -fri", "b", "c -mon", "d"]);
+        send(&mut app, Message::Filter(FilterType::General));
+        send(&mut app, Message::SelectLast);
+        send(&mut app, Message::Rewrite);
+        assert_eq!(app.input_buffer.value(), "d");
+        assert_eq!(app.update_cache.as_deref(), Some("2025-01-01 00:00:03"));
+    }
+
+    #[test]
+    fn filtered_reorder_test() {
+        let mut app = test_app("reorder", &["a -fri", "b", "c -mon", "d"]);
+        send(&mut app, Message::Filter(FilterType::Week));
+        send(&mut app, Message::SelectLast);
+        send(&mut app, Message::Reorder(Reorder::Up));
+        assert_eq!(texts(&app), ["c", "a", "b", "d"]);
+        assert_eq!(app.table_state.selected(), Some(0));
+        send(&mut app, Message::Reorder(Reorder::Bottom));
+        assert_eq!(texts(&app), ["a", "b", "d", "c"]);
+        assert_eq!(app.table_state.selected(), Some(3));
+        //没有可见的行时不移动
+        send(
+            &mut app,
+            Message::Filter(FilterType::Search("zzz".to_string())),
+        );
+        app.table_state.select(Some(0));
+        send(&mut app, Message::Reorder(Reorder::Bottom));
+        assert_eq!(texts(&app), ["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn duplicate_id_test() {
+        let mut app = test_app("duplicate", &["a", "b", "c"]);
+        let mut todo_list = app.todo_list.read().unwrap().clone();
+        todo_list[1].created_at = todo_list[0].created_at.clone();
+        todo_list[2].created_at = todo_list[0].created_at.clone();
+        App::write_json(&app.data_path(&TODO_LIST_PATH), &todo_list).unwrap();
+        //读取后创建时间各不相同，删除一个不会连带删除另一个
+        app.load_todo_list().unwrap();
+        let ids = app
+            .todo_list
+            .read()
+            .unwrap()
+            .iter()
+            .map(|todo| todo.created_at.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                "2025-01-01 00:00:00",
+                "2025-01-01 00:00:01",
+                "2025-01-01 00:00:02"
+            ]
+        );
+        send(&mut app, Message::SelectFirst);
+        send(&mut app, Message::Delete);
+        assert_eq!(texts(&app), ["b", "c"]);
+    }
+
+    #[test]
+    fn sorted_reorder_test() {
+        let mut app = test_app("sorted-reorder", &["c", "a", "b"]);
+        send(&mut app, Message::Sort(SortKey::Text));
+        send(&mut app, Message::SelectFirst);
+        //排序时移动会先回到手动调整的顺序，再移动选中的待办
+        send(&mut app, Message::Reorder(Reorder::Down));
+        assert_eq!(app.sort_key, SortKey::Manual);
+        assert_eq!(texts(&app), ["c", "b", "a"]);
+        assert_eq!(app.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn hidden_marks_test() {
+        let mut app = test_app("marks", &["a", "b -fri", "c"]);
+        send(&mut app, Message::SelectFirst);
+        send(&mut app, Message::Mark);
+        send(&mut app, Message::Select(2));
+        send(&mut app, Message::Mark);
+        //过滤后看不到的标记不受影响
+        send(&mut app, Message::Filter(FilterType::Week));
+        send(&mut app, Message::SelectFirst);
+        send(&mut app, Message::Tag("x".to_string(), true));
+        assert_eq!(texts(&app), ["a", "b", "c"]);
+        send(&mut app, Message::Filter(FilterType::All));
+        send(&mut app, Message::Delete);
+        assert_eq!(texts(&app), ["b"]);
+        //切换清单时清除标记
+        send(&mut app, Message::MarkAll);
+        send(&mut app, Message::SwitchList("work".to_string()));
+        assert!(app.marked.is_empty());
+    }
+
+    #[test]
+    fn render_snapshot_test() {
+        let mut app = test_app("snapshot", &["buy milk #home", "read book @3/10"]);
+        send(&mut app, Message::SelectFirst);
+        let lines = render(&mut app, 100, 16);
+        let snapshot = SNAPSHOT.lines().collect::<Vec<_>>();
+        assert_eq!(lines, snapshot, "\n{}", lines.join("\n"));
+    }
+
+    const SNAPSHOT: &str = r#"               | last save at: 1970-01-01 00:00:00 | last sync at: 1970-01-01 00:00:00
+┌─────────────────────────────────────────── InputEdit ────────────────────────────────────────────┐
+│                                                                                                  │
+└───────────────────────── Insert/Add <enter> Normal <esc> Complete <tab> ─────────────────────────┘
+        Deadline <text -mon/15/2025-03-01 14:30/tomorrow/every 2 weeks> Progress <text @3/10>
+┌──────────────────────────────────────────── TodoList ──────────── Total: 2 Filtered: 2 Marked: 0 ┐
+│                                                                                                  │
+│Index Content                       Kind                 State      Next       CreatedAt          │
+│                                                                                                  │
+│1     buy milk #home                General              NoDeadline            2025-01-01 00:00:00│
+│2     read book                     ██░░░░░░ 3/10        NoDeadline            2025-01-01 00:00:01│
+│                                                                                                  │
+│                                                                                                  │
+│                                                                                                  │
+│                                                                                                  │
+└─────── Next <↓> Previous <↑> First <home> Last <end> Move <C-↑/C-↓/C-home/C-end> More <?> ───────┘"#;
+
+    #[test]
+    fn responsive_layout_test() {
+        let mut app = test_app("responsive", &["buy milk #home", "gym -every 2 weeks"]);
+        send(&mut app, Message::SelectFirst);
+        send(&mut app, Message::Mark);
+        let screen = render(&mut app, 60, 20).join("\n");
+        assert!(screen.contains("│Content"));
+        assert!(!screen.contains("Index") && !screen.contains("CreatedAt"));
+        assert!(screen.contains("│* buy milk #home"));
+        assert!(screen.contains(" 2/2 *1 ┐"));
+
+        //很窄时只剩一列，没有表头，点击第二行选中第二个待办
+        let screen = render(&mut app, 40, 14);
+        assert!(screen[6].starts_with("│* buy milk #home  General"));
+        assert!(screen.iter().all(|line| !line.contains("Content")));
+        assert!(screen[13].ends_with(" More <?> ───┘"));
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 5,
+            row: app.table_area.y + 2,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+        assert!(matches!(app.handle_mouse(click), Some(Message::Select(1))));
+        let click = MouseEvent {
+            row: app.table_area.y + 5,
+            ..click
+        };
+        let selected = app.table_state.selected();
+        assert!(app.handle_mouse(click).is_none());
+        assert!(app.handle_mouse(click).is_none());
+        assert_eq!(app.table_state.selected(), selected);
+
+        //高度足够时帮助栏换行显示在表格下方
+        let screen = render(&mut app, 80, 40);
+        assert!(screen[39].contains("Filter <w/m/o/c/p/g/i/u/e/n/a>"));
+        assert_eq!(
+            pack_line(&[" a <1>", " b <2>", " c <3>"], 12),
+            [" a <1> b <2>", " c <3>"]
+        );
+    }
+
+    #[test]
+    fn script_insert_test() {
+        let mut app = test_app("insert", &[]);
+        press(
+            &mut app,
+            "<enter>buy milk #home<enter>call mom -tomorrow<enter><esc>",
+        );
+        assert_eq!(texts(&app), ["buy milk #home", "call mom"]);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.table_state.selected(), Some(1));
+        let screen = screen(&mut app);
+        assert!(screen.contains("Once: "), "{}", screen);
+
+        //解析失败时保留输入并显示错误
+        press(&mut app, "<enter>gym -someday<enter>");
+        assert_eq!(app.input_mode, InputMode::Insert);
+        assert_eq!(app.input_buffer.value(), "gym -someday");
+        assert!(app.input_error.is_some());
+        press(&mut app, "<esc>");
+        assert_eq!(texts(&app).len(), 2);
+    }
+
+    #[test]
+    fn script_edit_test() {
+        let mut app = test_app("edit", &["a", "b -fri", "c @3/10"]);
+        //改写后的待办留在原位，创建时间不变
+        press(&mut app, "<down><down>r!<enter><esc>");
+        assert_eq!(texts(&app), ["a!", "b", "c"]);
+        assert_eq!(
+            app.todo_list.read().unwrap()[0].created_at,
+            "2025-01-01 00:00:00"
+        );
+        press(&mut app, "<down><down>+");
+        assert_eq!(
+            app.todo_list.read().unwrap()[2].kind.print_info(),
+            TodoKind::Progress("4/10".to_string().into()).print_info()
+        );
+        //不是进度类型的待办不记录撤销
+        let undo_len = app.undo_history.len();
+        press(&mut app, "<up><up>+-<down><down>");
+        assert_eq!(app.undo_history.len(), undo_len);
+        press(&mut app, "<c-z><c-z><c-z>");
+        assert_eq!(texts(&app), ["a", "b", "c"]);
+        press(&mut app, "<down>d");
+        assert_eq!(texts(&app), ["b", "c"]);
+        press(&mut app, "<down><space><space>d");
+        assert!(texts(&app).is_empty());
+    }
+
+    #[test]
+    fn script_search_and_palette_test() {
+        let mut app = test_app("search", &["pears", "apples", "bananas"]);
+        press(&mut app, "/an");
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(app.visible_indices(), [2]);
+        assert!(screen(&mut app).contains(" Search "));
+        press(&mut app, "<esc>");
+        assert_eq!(app.visible_indices().len(), 3);
+
+        //排序只改变显示的顺序，保存的顺序不变
+        press(&mut app, ":sort text<enter>");
+        assert_eq!(rows(&app), ["apples", "bananas", "pears"]);
+        assert_eq!(texts(&app), ["pears", "apples", "bananas"]);
+        assert!(app.undo_history.is_empty());
+        assert!(!app.data_path(&TODO_LIST_PATH).exists());
+        //再次点击同一列的表头时回到手动调整的顺序
+        render(&mut app, 120, 30);
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: app.table_area.x + 8,
+            row: app.table_area.y + 2,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+        let msg = app.handle_mouse(click).unwrap();
+        assert!(matches!(msg, Message::Sort(SortKey::Manual)));
+        send(&mut app, msg);
+        assert_eq!(rows(&app), ["pears", "apples", "bananas"]);
+        let msg = app.handle_mouse(click).unwrap();
+        send(&mut app, msg);
+        assert_eq!(rows(&app), ["apples", "bananas", "pears"]);
+        press(&mut app, ":srt<tab><tab><enter>");
+        assert_eq!(app.input_mode, InputMode::Command);
+        press(&mut app, "<esc>:flt<enter>");
+        assert_eq!(app.input_buffer.value(), "filter ");
+        press(&mut app, "bogus<enter>");
+        assert!(app.input_error.is_some());
+
+        press(&mut app, "<esc>");
+        send(&mut app, Message::SelectFirst);
+        let undo_len = app.undo_history.len();
+        press(&mut app, ":kind fri<enter>");
+        let todo_list = app.todo_list.read().unwrap().clone();
+        assert_eq!(todo_list[1].kind, TodoKind::Week(chrono::Weekday::Fri));
+        assert_eq!(todo_list[0].kind, TodoKind::General);
+        press(&mut app, ":kind someday<enter>");
+        assert!(app.input_error.is_some());
+        assert_eq!(app.undo_history.len(), undo_len + 1);
+    }
+
+    #[test]
+    fn script_log_test() {
+        let mut app = test_app("log", &["a"]);
+        press(&mut app, "<enter>tea -someday<enter>");
+        assert!(app.input_error.is_some());
+        assert!(render(&mut app, 120, 30)[0].starts_with("cannot parse \"-someday\""));
+        press(&mut app, "<esc>:export /nonexistent/dir/out.txt<enter>");
+        press(&mut app, "L");
+        let screen = screen(&mut app);
+        assert!(screen.contains(" Messages "));
+        assert!(screen.contains("WARN  cannot parse"));
+        //最新的消息在最上面
+        assert!(screen.find("ERROR Export").unwrap() < screen.find("WARN").unwrap());
+        press(&mut app, "j<esc>");
+        assert_eq!(app.log_scroll, None);
+    }
+
+    #[test]
+    fn reminder_test() {
+        let mut app = test_app("reminder", &["a -2025-01-10 09:00", "b -2025-01-10 09:05"]);
+        let at = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2025-01-10 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap()
+        };
+        //同时到时间的提醒都会显示，不会互相覆盖
+        app.tick_at(at("08:56"));
+        assert_eq!(app.alerts.len(), 2);
+        assert!(screen(&mut app).contains(" Reminder (1/2) "));
+        press(&mut app, "x");
+        assert_eq!(
+            app.alerts.front().map(String::as_str),
+            Some("b (Once: 2025-01-10 09:05)")
+        );
+        press(&mut app, "x");
+        assert!(app.alerts.is_empty());
+        app.tick_at(at("08:57"));
+        assert!(app.alerts.is_empty());
+
+        //改了时间之后重新提醒
+        send(&mut app, Message::SelectFirst);
+        send(
+            &mut app,
+            Message::ChangeKind("-2025-01-10 09:30".to_string()),
+        );
+        app.tick_at(at("09:20"));
+        assert_eq!(app.alerts.len(), 1);
+    }
+
+    #[test]
+    fn sync_status_test() {
+        let mut app = test_app("sync-status", &["a"]);
+        *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+        app.update(Message::Sync);
+        let notices = app.notices.read().unwrap().clone();
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].text, "Sync already in progress");
+        assert!(render(&mut app, 120, 30)[0].contains(" transferring"));
+        *app.sync_status.write().unwrap() = SyncStatus::Failed("no server found".to_string());
+        assert!(render(&mut app, 120, 30)[0].ends_with("| sync failed: no server found"));
+        *app.sync_status.write().unwrap() = SyncStatus::Succeeded(SyncAction::Download);
+        assert!(render(&mut app, 120, 30)[0].ends_with("1970-01-01 00:00:00 (downloaded)"));
+    }
+
+    #[test]
+    fn auto_sync_test() {
+        let mut app = test_app("auto-sync", &["a", "b"]);
+        let now = Instant::now();
+        app.last_sync = Some(now);
+        assert!(!app.sync_due(now + Duration::from_secs(3600)));
+        app.config.sync = SyncConfig {
+            on_start: true,
+            interval_minutes: 10,
+            debounce_seconds: 5,
+            on_quit: true,
+        };
+        send(&mut app, Message::AddText("c".to_string()));
+        let changed_at = app.changed_at.unwrap();
+        assert!(!app.sync_due(changed_at + Duration::from_secs(4)));
+        assert!(app.sync_due(changed_at + Duration::from_secs(5)));
+        app.changed_at = None;
+        assert!(!app.sync_due(now + Duration::from_secs(599)));
+        assert!(app.sync_due(now + Duration::from_secs(600)));
+
+        let server = || {
+            Some((
+                SyncAction::Download,
+                SyncState::default(),
+                vec![Todo::new("x").unwrap()],
+            ))
+        };
+        //同步期间添加的待办不会被下载的数据覆盖，之后马上再同步一次
+        *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+        send(&mut app, Message::AddText("d".to_string()));
+        let (action, sync_state, todo_list) = server().unwrap();
+        app.apply_sync(action, sync_state, todo_list);
+        assert_eq!(texts(&app), ["a", "b", "c", "d"]);
+        assert!(!app.sync_status.read().unwrap().is_running());
+        assert!(app.sync_due(now));
+
+        //假装重新同步已经开始，不访问网络
+        app.resync_pending = false;
+        app.changed_at = None;
+        *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+        *app.sync_result.write().unwrap() = server();
+        app.poll_sync(now);
+        assert_eq!(texts(&app), ["x"]);
+        assert_eq!(
+            *app.sync_status.read().unwrap(),
+            SyncStatus::Succeeded(SyncAction::Download)
+        );
+        send(&mut app, Message::Undo);
+        assert_eq!(texts(&app), ["a", "b", "c", "d"]);
+
+        //退出前等待正在进行的同步结束
+        *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+        send(&mut app, Message::Quit);
+        app.poll_sync(now);
+        assert!(!app.exit);
+        *app.sync_status.write().unwrap() = SyncStatus::Idle;
+        app.poll_sync(now);
+        assert!(app.exit);
+    }
+
+    //默认配置没有防抖和定时同步，同步期间的修改仍然会再同步一次
+    #[test]
+    fn resync_test() {
+        let mut app = test_app("resync", &["a"]);
+        let now = Instant::now();
+        app.last_sync = Some(now);
+        for action in [SyncAction::Upload, SyncAction::Download] {
+            *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+            send(&mut app, Message::AddText("b".to_string()));
+            assert!(!app.sync_due(now));
+            app.apply_sync(action, SyncState::default(), vec![]);
+            assert!(app.resync_pending);
+            assert!(app.sync_due(now));
+            app.resync_pending = false;
+            app.changed_at = None;
+        }
+        //同步期间没有修改时不会再同步
+        app.apply_sync(SyncAction::Upload, SyncState::default(), vec![]);
+        assert!(!app.sync_due(now + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn storage_error_test() {
+        let mut app = test_app("storage", &["a"]);
+        let path = app.data_path(&TODO_LIST_PATH);
+        std::fs::write(&path, "[{").unwrap();
+        let err = app.load_todo_list().unwrap_err();
+        assert!(matches!(err, Error::Storage(..)));
+        assert!(err.to_string().starts_with(&path.display().to_string()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(app.load_todo_list().is_ok());
+
+        //配置格式有误时提示并使用默认配置
+        let config_path = app.data_path(&CONFIG_PATH);
+        std::fs::write(&config_path, r#"{"sync":{"on_start":0}}"#).unwrap();
+        assert!(app.load_config(&config_path).sync.on_start);
+        let notice = app.notices.read().unwrap().last().cloned().unwrap();
+        assert_eq!(notice.level, Level::Warn);
+        assert!(notice.text.starts_with("Ignored config: "));
+
+        //保存失败时第一次退出会被拦下
+        app.data_dir = Some(path.join("missing"));
+        send(&mut app, Message::Quit);
+        assert!(!app.exit);
+        let notice = app.notices.read().unwrap().last().cloned().unwrap();
+        assert_eq!(notice.level, Level::Error);
+        assert!(notice.text.ends_with("quit again to discard changes"));
+        send(&mut app, Message::Quit);
+        assert!(app.exit);
+    }
+
+    #[test]
+    fn script_views_test() {
+        let mut app = test_app("views", &["gym -fri", "milk"]);
+        press(&mut app, "?");
+        assert!(screen(&mut app).contains(" Help "));
+        press(&mut app, "jj<esc>");
+        assert_eq!(app.help_scroll, None);
+        press(&mut app, "vc");
+        assert!(screen(&mut app).contains("Mo   Tu   We   Th   Fr   Sa   Su"));
+        press(&mut app, "<esc>va");
+        assert!(screen(&mut app).contains("No deadline"));
+        press(&mut app, "<esc>vs");
+        assert!(screen(&mut app).contains("By kind"));
+        press(&mut app, "<esc>");
+        assert_eq!(app.view, View::Table);
+        press(&mut app, "q");
+        assert!(app.exit);
+    }
+
+    //完整运行一次主循环，配置从数据目录读取并关闭启动时的同步
+    #[test]
+    fn run_with_test() {
+        let mut app = test_app("run", &["a"]);
+        let config_path = app.data_path(&CONFIG_PATH);
+        let config = r#"{"sync":{"on_start":false},"agenda":{"days":3},"month_end":"Skip"}"#;
+        std::fs::write(&config_path, config).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut events = ScriptedEvents::new("<enter>milk #home<enter><esc>q");
+        app.run_with(&mut terminal, &mut events).unwrap();
+        assert!(app.exit);
+        assert!(!app.config.sync.on_start);
+        assert_eq!(app.config.agenda.days, 3);
+        assert_eq!(app.config.month_end, MonthEnd::Skip);
+        assert_eq!(*app.sync_status.read().unwrap(), SyncStatus::Idle);
+        let saved = std::fs::read(app.data_path(&TODO_LIST_PATH)).unwrap();
+        let saved = serde_json::from_slice::<Vec<Todo>>(&saved).unwrap();
+        assert_eq!(saved[1].text, "milk #home");
+        let buffer = terminal.backend().buffer();
+        let screen = buffer
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+        assert!(screen.contains("milk #home"));
+    }
+
+    #[test]
+    fn interval_sync_without_start_test() {
+        let mut app = test_app("interval", &["a"]);
+        let config = r#"{"sync":{"on_start":false,"interval_minutes":1}}"#;
+        std::fs::write(app.data_path(&CONFIG_PATH), config).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        app.run_with(&mut terminal, &mut ScriptedEvents::new("q"))
+            .unwrap();
+        assert!(app.exit);
+        assert_eq!(*app.sync_status.read().unwrap(), SyncStatus::Idle);
+        let started = app.last_sync.unwrap();
+        assert!(!app.sync_due(started + Duration::from_secs(59)));
+        assert!(app.sync_due(started + Duration::from_secs(60)));
+    }
+
+    //每个消息都执行一遍并渲染，检查各自的效果
+    #[test]
+    fn every_message_test() {
+        let export = std::env::temp_dir().join(format!("ttd-export-{}.txt", std::process::id()));
+        let today = chrono::Local::now().date_naive();
+        let kind = |app: &App, index: usize| app.todo_list.read().unwrap()[index].kind.print_info();
+        type Check = Box<dyn Fn(&App) -> bool>;
+        let cases: Vec<(Message, Check)> = vec![
+            (Message::Add, Box::new(|app| texts(app).len() == 4)),
+            (
+                Message::AddText("tea -tomorrow".to_string()),
+                Box::new(|app| texts(app)[4] == "tea" && app.table_state.selected() == Some(4)),
+            ),
+            (
+                Message::Delete,
+                Box::new(|app| texts(app) == ["a #x", "b", "d"]),
+            ),
+            (
+                Message::Done,
+                Box::new(|app| texts(app) == ["a #x", "b", "d"] && app.history.len() == 1),
+            ),
+            (
+                Message::Mark,
+                Box::new(|app| app.marked.len() == 1 && app.table_state.selected() == Some(1)),
+            ),
+            (Message::MarkAll, Box::new(|app| app.marked.len() == 4)),
+            (Message::ClearMarks, Box::new(|app| app.marked.is_empty())),
+            (
+                Message::Tag("tag".to_string(), true),
+                Box::new(|app| texts(app)[0] == "c #tag"),
+            ),
+            (
+                Message::MoveTo("work".to_string()),
+                Box::new(|app| app.todo_list.read().unwrap()[0].list == "work"),
+            ),
+            (
+                Message::ChangeKind("-fri".to_string()),
+                Box::new(move |app| kind(app, 0) == "Week: Fri"),
+            ),
+            (
+                Message::Save,
+                Box::new(|app| app.data_path(&TODO_LIST_PATH).exists()),
+            ),
+            (
+                Message::Rewrite,
+                Box::new(|app| {
+                    app.input_mode == InputMode::Insert && app.input_buffer.value() == "c @ 3/10"
+                }),
+            ),
+            (Message::Undo, Box::new(|app| texts(app).len() == 4)),
+            (
+                Message::Filter(FilterType::Progress),
+                Box::new(|app| app.visible_indices() == [0]),
+            ),
+            (
+                Message::Filter(FilterType::Day(today)),
+                Box::new(|app| app.visible_indices().contains(&3)),
+            ),
+            (
+                Message::InputModeChange(InputMode::Insert),
+                Box::new(|app| app.input_mode == InputMode::Insert),
+            ),
+            (
+                Message::CancelSearch,
+                Box::new(|app| app.input_mode == InputMode::Normal),
+            ),
+            //没有输入时补全成第一个命令
+            (
+                Message::RunCommand,
+                Box::new(|app| app.input_buffer.value() == "add "),
+            ),
+            (
+                Message::Sort(SortKey::Text),
+                Box::new(|app| {
+                    rows(app) == ["a #x", "b", "c", "d"]
+                        && texts(app) == ["c", "a #x", "b", "d"]
+                        && app.table_state.selected() == Some(2)
+                }),
+            ),
+            (
+                Message::Reorder(Reorder::Down),
+                Box::new(|app| texts(app) == ["a #x", "c", "b", "d"]),
+            ),
+            (
+                Message::SwitchList("work".to_string()),
+                Box::new(|app| app.current_list == "work" && app.visible_indices().is_empty()),
+            ),
+            (
+                Message::Export(export.to_string_lossy().to_string()),
+                Box::new(|app| app.notices.read().unwrap()[0].text.starts_with("Exported")),
+            ),
+            (
+                Message::SelectPrevious,
+                Box::new(|app| app.table_state.selected() == Some(3)),
+            ),
+            (
+                Message::SelectNext,
+                Box::new(|app| app.table_state.selected() == Some(1)),
+            ),
+            (
+                Message::SelectFirst,
+                Box::new(|app| app.table_state.selected() == Some(0)),
+            ),
+            (
+                Message::Select(2),
+                Box::new(|app| app.table_state.selected() == Some(2)),
+            ),
+            (
+                Message::SelectLast,
+                Box::new(|app| app.table_state.selected() == Some(3)),
+            ),
+            (
+                Message::StepProgress(true),
+                Box::new(move |app| kind(app, 0).ends_with("4/10")),
+            ),
+            (Message::DismissAlert, Box::new(|app| app.alerts.is_empty())),
+            (
+                Message::ToggleHelp,
+                Box::new(|app| app.help_scroll == Some(0)),
+            ),
+            (
+                Message::SwitchView(View::Calendar),
+                Box::new(move |app| app.view == View::Calendar && app.calendar_date == today),
+            ),
+            (
+                Message::SwitchView(View::Agenda),
+                Box::new(|app| app.view == View::Agenda),
+            ),
+            (
+                Message::SwitchView(View::Stats),
+                Box::new(|app| app.view == View::Stats),
+            ),
+            (
+                Message::MoveDay(-3),
+                Box::new(|app| app.calendar_date.to_string() == "1969-12-29"),
+            ),
+            (
+                Message::MoveMonth(1),
+                Box::new(|app| app.calendar_date.to_string() == "1970-02-01"),
+            ),
+            (
+                Message::ScrollAgenda(5),
+                Box::new(|app| app.agenda_scroll == 5),
+            ),
+            //默认日期没有待办
+            (
+                Message::SelectDay,
+                Box::new(|app| app.view == View::Table && app.visible_indices().is_empty()),
+            ),
+            //帮助和日志没有打开时不滚动
+            (
+                Message::ScrollHelp(3),
+                Box::new(|app| app.help_scroll.is_none()),
+            ),
+            (
+                Message::ToggleLog,
+                Box::new(|app| app.log_scroll == Some(0)),
+            ),
+            (
+                Message::ScrollLog(3),
+                Box::new(|app| app.log_scroll.is_none()),
+            ),
+            (
+                Message::Complete(true),
+                Box::new(|app| app.completion.is_none()),
+            ),
+            (
+                Message::AcceptCompletion,
+                Box::new(|app| app.input_buffer.value().is_empty()),
+            ),
+            (
+                Message::Sync,
+                Box::new(|app| app.notices.read().unwrap()[0].text == "Sync already in progress"),
+            ),
+            (Message::Quit, Box::new(|app| app.exit)),
+        ];
+        for (msg, check) in cases {
+            let mut app = test_app("every", &["c @3/10", "a #x", "b -fri", "d -daily"]);
+            send(&mut app, Message::SelectFirst);
+            if matches!(msg, Message::Sync) {
+                *app.sync_status.write().unwrap() = SyncStatus::Transferring;
+            }
+            send(&mut app, msg.clone());
+            assert!(check(&app), "{:?}", msg);
+            render(&mut app, 80, 24);
+            render(&mut app, 30, 10);
+        }
+        assert!(export.exists());
+        let _ = std::fs::remove_file(export);
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+use crate::todo::{MonthEnd, Todo};
+
+//以周一开头的月历，每行一周，不属于本月的日期为None
+pub fn month_grid(date: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let Some(first) = date.with_day(1) else {
+        return vec![];
+    };
+    let start = first - Days::new(first.weekday().num_days_from_monday() as u64);
+    let mut weeks = vec![];
+    for week in start.iter_weeks() {
+        if week > first && week.month() != first.month() {
+            break;
+        }
+        let mut days = [None; 7];
+        for (offset, day) in week.iter_days().take(7).enumerate() {
+            days[offset] = (day.month() == first.month()).then_some(day);
+        }
+        weeks.push(days);
+    }
+    weeks
+}
+
+//切换月份时尽量保持同一天，超出天数时取月末
+pub fn shift_month(date: NaiveDate, months: i32) -> NaiveDate {
+    let shifted = if months >= 0 {
+        date.checked_add_months(Months::new(months as u32))
+    } else {
+        date.checked_sub_months(Months::new(months.unsigned_abs()))
+    };
+    shifted.unwrap_or(date)
+}
+
+pub fn items_on<'a>(
+    todo_list: &'a [Todo],
+    date: NaiveDate,
+    list: &str,
+    month_end: MonthEnd,
+) -> Vec<&'a Todo> {
+    todo_list
+        .iter()
+        .filter(|todo| todo.list == list && todo.kind.occurs_on(date, month_end))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoKind;
+
+    fn date(input: &str) -> NaiveDate {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn calendar_test() {
+        // 2025-02-01 是星期六，28天跨5周
+        let grid = month_grid(date("2025-02-14"));
+        assert_eq!(grid.len(), 5);
+        assert_eq!(grid[0][..5], [None; 5]);
+        assert_eq!(grid[0][5], Some(date("2025-02-01")));
+        assert_eq!(grid[4][4], Some(date("2025-02-28")));
+        assert_eq!(grid[4][5], None);
+        // 2026-06-01 是星期一
+        let grid = month_grid(date("2026-06-30"));
+        assert_eq!(grid.len(), 5);
+        assert_eq!(grid[0][0], Some(date("2026-06-01")));
+
+        assert_eq!(shift_month(date("2025-01-31"), 1), date("2025-02-28"));
+        assert_eq!(shift_month(date("2025-01-31"), -2), date("2024-11-30"));
+
+        let todo_list = [
+            Todo {
+                text: "gym".to_string(),
+                kind: TodoKind::Week(chrono::Weekday::Fri),
+                ..Default::default()
+            },
+            Todo {
+                text: "dentist".to_string(),
+                kind: TodoKind::Once("2025-02-14".to_string().try_into().unwrap()),
+                ..Default::default()
+            },
+            Todo {
+                text: "milk".to_string(),
+                ..Default::default()
+            },
+        ];
+        let items = items_on(&todo_list, date("2025-02-14"), "", MonthEnd::Clamp);
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items_on(&todo_list, date("2025-02-13"), "", MonthEnd::Clamp).len(),
+            0
+        );
+        assert_eq!(
+            items_on(&todo_list, date("2025-02-14"), "work", MonthEnd::Clamp).len(),
+            0
+        );
+    }
+}
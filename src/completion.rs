@@ -0,0 +1,207 @@
+use crate::todo::{ProgressFormat, Todo, TodoKind};
+
+const DATE_PHRASES: [&str; 30] = [
+    "today",
+    "tomorrow",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+    "next monday",
+    "next friday",
+    "next week",
+    "next month",
+    "this friday",
+    "in 3 days",
+    "in 1 week",
+    "in 1 month",
+    "end of week",
+    "end of month",
+    "end of year",
+    "daily",
+    "weekdays",
+    "weekends",
+    "weekly on mon",
+    "every 2 weeks",
+    "every 3 days",
+    "first monday of the month",
+    "last friday of the month",
+    "yearly on jan 1",
+    "every monday",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Completion {
+    //被替换部分在输入框中的起始位置（按字符计）
+    pub start: usize,
+    pub candidates: Vec<String>,
+    pub selected: usize,
+}
+
+impl Completion {
+    //根据光标前的内容决定补全哪一类：#标签、-后的日期、@后的进度
+    pub fn new(input: &str, cursor: usize, todo_list: &[Todo]) -> Option<Self> {
+        let before = input.chars().take(cursor).collect::<String>();
+        //全角空格等多字节空白不能直接+1
+        let word_start = before
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        let word = &before[word_start..];
+        let (typed, candidates) = if word.starts_with('#') {
+            (word, Self::tags(todo_list))
+        } else {
+            match Todo::split_input(&before) {
+                (_, Some((delimiter, _))) => {
+                    let pattern = if delimiter == '-' { " -" } else { " @" };
+                    let typed = before[before.rfind(pattern)? + 2..].trim_start();
+                    if delimiter == '-' {
+                        (typed, DATE_PHRASES.map(String::from).to_vec())
+                    } else {
+                        (typed, Self::progress_formats(todo_list))
+                    }
+                }
+                (_, None) => {
+                    //刚输入分隔符，后面还没有内容
+                    let typed = "";
+                    if before.trim_end().ends_with(" -") {
+                        (typed, DATE_PHRASES.map(String::from).to_vec())
+                    } else if before.trim_end().ends_with(" @") {
+                        (typed, Self::progress_formats(todo_list))
+                    } else {
+                        return None;
+                    }
+                }
+            }
+        };
+        let lowercase = typed.to_lowercase();
+        let candidates = candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate.to_lowercase().starts_with(&lowercase) && *candidate != typed
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Completion {
+            start: cursor - typed.chars().count(),
+            candidates,
+            selected: 0,
+        })
+    }
+
+    pub fn select(&mut self, forward: bool) {
+        let len = self.candidates.len();
+        self.selected = if forward {
+            (self.selected + 1) % len
+        } else {
+            (self.selected + len - 1) % len
+        };
+    }
+
+    //返回补全后的内容和新的光标位置
+    pub fn apply(&self, input: &str, cursor: usize) -> (String, usize) {
+        let candidate = &self.candidates[self.selected];
+        let mut value = input.chars().take(self.start).collect::<String>();
+        value.push_str(candidate);
+        let new_cursor = value.chars().count();
+        value.extend(input.chars().skip(cursor));
+        (value, new_cursor)
+    }
+
+    fn tags(todo_list: &[Todo]) -> Vec<String> {
+        let mut tags = todo_list
+            .iter()
+            .flat_map(|todo| todo.text.split_whitespace())
+            .filter(|word| word.len() > 1 && word.starts_with('#'))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn progress_formats(todo_list: &[Todo]) -> Vec<String> {
+        let mut formats = todo_list
+            .iter()
+            .filter_map(|todo| match todo.kind {
+                TodoKind::Progress(ref progress) => match progress.format {
+                    ProgressFormat::Fraction => Some(format!("0/{}", progress.total)),
+                    ProgressFormat::Percent => Some("0%".to_string()),
+                    ProgressFormat::Of(ref unit) if !unit.is_empty() => {
+                        Some(format!("{} 1 of {}", unit, progress.total))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        formats.sort();
+        formats.dedup();
+        formats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(text: &str, kind: TodoKind) -> Todo {
+        Todo {
+            text: text.to_string(),
+            kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn completion_test() {
+        let todo_list = [
+            todo("buy milk #home", TodoKind::General),
+            todo(
+                "read #book",
+                TodoKind::Progress("chapter 3 of 12".to_string().into()),
+            ),
+            todo(
+                "fix #bug #home",
+                TodoKind::Progress("2/5".to_string().into()),
+            ),
+        ];
+        let input = "clean #h";
+        let completion = Completion::new(input, 8, &todo_list).unwrap();
+        assert_eq!(completion.candidates, ["#home"]);
+        assert_eq!(completion.apply(input, 8), ("clean #home".to_string(), 11));
+
+        let input = "call mom -next";
+        let completion = Completion::new(input, 14, &todo_list).unwrap();
+        assert_eq!(
+            completion.candidates,
+            ["next monday", "next friday", "next week", "next month"]
+        );
+        assert_eq!(completion.start, 10);
+
+        let input = "write report @ ";
+        let completion = Completion::new(input, 15, &todo_list).unwrap();
+        assert_eq!(completion.candidates, ["0/5", "chapter 1 of 12"]);
+
+        let input = "a -tue x";
+        let completion = Completion::new(input, 6, &todo_list).unwrap();
+        assert_eq!(completion.candidates, ["tuesday"]);
+        assert_eq!(completion.apply(input, 6), ("a -tuesday x".to_string(), 10));
+
+        assert_eq!(Completion::new("plain text", 10, &todo_list), None);
+
+        let input = "买牛奶\u{3000}#h";
+        let completion = Completion::new(input, 6, &todo_list).unwrap();
+        assert_eq!(completion.candidates, ["#home"]);
+        assert_eq!(
+            completion.apply(input, 6),
+            ("买牛奶\u{3000}#home".to_string(), 9)
+        );
+        assert_eq!(Completion::new("买牛奶\u{3000}", 4, &todo_list), None);
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::{io, path::Path};
+
+use crate::{
+    error::{Error, Result},
+    keymap::KeymapConfig,
+    theme::ThemeConfig,
+    todo::MonthEnd,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub reminder: ReminderConfig,
+    pub month_end: MonthEnd,
+    pub keymap: KeymapConfig,
+    pub theme: ThemeConfig,
+    pub agenda: AgendaConfig,
+    pub sync: SyncConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReminderConfig {
+    pub enabled: bool,
+    pub minutes_before: i64,
+    pub command: Option<String>,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        ReminderConfig {
+            enabled: true,
+            minutes_before: 10,
+            command: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AgendaConfig {
+    pub days: u32,
+}
+
+impl Default for AgendaConfig {
+    fn default() -> Self {
+        AgendaConfig { days: 7 }
+    }
+}
+
+//为0时关闭对应的自动同步，debounce_seconds是最后一次修改后等待的时间
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub on_start: bool,
+    pub interval_minutes: u64,
+    pub debounce_seconds: u64,
+    pub on_quit: bool,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        SyncConfig {
+            on_start: true,
+            interval_minutes: 0,
+            debounce_seconds: 0,
+            on_quit: false,
+        }
+    }
+}
+
+impl Config {
+    //配置文件不存在或者为空时使用默认配置，格式有误时返回错误
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(raw) if raw.is_empty() => Ok(Config::default()),
+            Ok(raw) => serde_json::from_slice(&raw).map_err(|err| Error::storage(path, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(Error::storage(path, err)),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::NaiveDateTime;
+use crossterm::event::DisableMouseCapture;
+use std::{
+    backtrace::Backtrace,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, TryLockError},
+};
+
+use crate::{
+    error::{Error, Result},
+    notice::{self, Level, NoticeLog},
+    todo::Todo,
+};
+
+//崩溃时先恢复终端，再把内存中的待办和崩溃报告写到数据目录
+pub fn install_panic_hook(dir: PathBuf, todo_list: Arc<RwLock<Vec<Todo>>>, notices: NoticeLog) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture();
+        let thread = std::thread::current();
+        let thread = thread.name().unwrap_or("unnamed");
+        let now = chrono::Local::now().naive_local();
+        let report = crash_report(&info.to_string(), thread, &backtrace, now);
+        let saved = save_crash(&dir, snapshot(&todo_list), &report, now);
+        //后台线程崩溃时界面还在运行，不恢复终端，在消息日志中告诉用户报告的位置
+        if thread != "main" {
+            notice::try_push(&notices, Level::Error, background_notice(&saved));
+            return;
+        }
+        let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+        ratatui::restore();
+        match saved {
+            Ok((report, Some(todo_list))) => eprintln!(
+                "crash report written to {}\ntodo list saved to {}",
+                report.display(),
+                todo_list.display()
+            ),
+            Ok((report, None)) => eprintln!("crash report written to {}", report.display()),
+            Err(err) => eprintln!("cannot write crash report: {}", err),
+        }
+        previous(info);
+    }));
+}
+
+fn background_notice(saved: &Result<(PathBuf, Option<PathBuf>)>) -> String {
+    match saved {
+        Ok((report, _)) => format!(
+            "Background task crashed, restart recommended, report written to {}",
+            report.display()
+        ),
+        Err(err) => format!(
+            "Background task crashed, restart recommended, cannot write crash report: {}",
+            err
+        ),
+    }
+}
+
+//崩溃的线程可能正持有写锁，这时拿不到待办
+fn snapshot(todo_list: &RwLock<Vec<Todo>>) -> Option<Vec<Todo>> {
+    match todo_list.try_read() {
+        Ok(todo_list) => Some(todo_list.clone()),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner().clone()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+fn crash_report(message: &str, thread: &str, backtrace: &Backtrace, now: NaiveDateTime) -> String {
+    format!(
+        "{} v{} crashed at {}\nthread: {}\n{}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        now.format("%Y-%m-%d %H:%M:%S"),
+        thread,
+        message,
+        backtrace
+    )
+}
+
+//返回报告和紧急保存的待办的路径
+fn save_crash(
+    dir: &Path,
+    todo_list: Option<Vec<Todo>>,
+    report: &str,
+    now: NaiveDateTime,
+) -> Result<(PathBuf, Option<PathBuf>)> {
+    std::fs::create_dir_all(dir).map_err(|err| Error::storage(dir, err))?;
+    let report_path = dir.join(format!("crash-{}.log", now.format("%Y%m%d-%H%M%S")));
+    std::fs::write(&report_path, report).map_err(|err| Error::storage(&report_path, err))?;
+    let Some(todo_list) = todo_list else {
+        return Ok((report_path, None));
+    };
+    let todo_list_path = dir.join("emergency_todo_list.json");
+    let file = std::fs::File::create(&todo_list_path)
+        .map_err(|err| Error::storage(&todo_list_path, err))?;
+    serde_json::to_writer(file, &todo_list).map_err(|err| Error::storage(&todo_list_path, err))?;
+    Ok((report_path, Some(todo_list_path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crash_test() {
+        let dir = std::env::temp_dir().join(format!("ttd-test-crash-{}", std::process::id()));
+        let now = NaiveDateTime::parse_from_str("2025-01-01 12:00:00", "%Y-%m-%d %H:%M:%S");
+        let now = now.unwrap();
+        let report = crash_report("boom", "main", &Backtrace::disabled(), now);
+        assert!(report.contains("crashed at 2025-01-01 12:00:00\nthread: main\nboom\n"));
+
+        let todo_list = RwLock::new(vec![Todo::new("milk").unwrap()]);
+        let guard = todo_list.write().unwrap();
+        assert!(snapshot(&todo_list).is_none());
+        drop(guard);
+        let (report_path, todo_list_path) =
+            save_crash(&dir, snapshot(&todo_list), &report, now).unwrap();
+        assert!(report_path.ends_with("crash-20250101-120000.log"));
+        assert_eq!(std::fs::read_to_string(report_path).unwrap(), report);
+        let saved = std::fs::read(todo_list_path.unwrap()).unwrap();
+        let saved = serde_json::from_slice::<Vec<Todo>>(&saved).unwrap();
+        assert_eq!(saved[0].text, "milk");
+
+        //后台线程崩溃时只记录消息，消息日志的锁被占用时放弃
+        let saved = save_crash(&dir, None, &report, now);
+        let text = background_notice(&saved);
+        assert!(text.ends_with("crash-20250101-120000.log"), "{}", text);
+        let notices = NoticeLog::default();
+        assert!(notice::try_push(&notices, Level::Error, text));
+        assert_eq!(notices.read().unwrap()[0].level, Level::Error);
+        let guard = notices.write().unwrap();
+        assert!(!notice::try_push(&notices, Level::Error, "ignored"));
+        drop(guard);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, Weekday};
+
+use crate::todo::Deadline;
+
+//解析相对日期和自然语言日期，结果都相对于today计算
+pub fn parse_relative(input: &str, today: NaiveDate) -> Option<Deadline> {
+    let input = input.trim().to_lowercase();
+    let mut tokens = input.split_whitespace().collect::<Vec<_>>();
+    let time = match tokens.as_slice() {
+        [.., time] if time.contains(':') => {
+            let time = NaiveTime::parse_from_str(time, "%H:%M").ok()?;
+            tokens.truncate(tokens.len() - 1);
+            if tokens.last() == Some(&"at") {
+                tokens.pop();
+            }
+            Some(time)
+        }
+        _ => None,
+    };
+    let date = match tokens.as_slice() {
+        ["today" | "tod"] => today,
+        ["tomorrow" | "tmr" | "tmrw"] => today + Days::new(1),
+        ["this", weekday] => weekday_after(today, weekday.parse().ok()?, false),
+        ["next", "week"] => today + Days::new(7),
+        ["next", "month"] => today.checked_add_months(Months::new(1))?,
+        ["next", "year"] => today.checked_add_months(Months::new(12))?,
+        ["next", weekday] => weekday_after(today, weekday.parse().ok()?, true),
+        ["in", n, unit] => {
+            let n = n.parse::<u32>().ok()?;
+            match unit.trim_end_matches('s') {
+                "day" => today.checked_add_days(Days::new(n as u64))?,
+                "week" => today.checked_add_days(Days::new(n as u64 * 7))?,
+                "month" => today.checked_add_months(Months::new(n))?,
+                "year" => today.checked_add_months(Months::new(n.checked_mul(12)?))?,
+                _ => return None,
+            }
+        }
+        ["end", "of", "week"] | ["eow"] => weekday_after(today, Weekday::Sun, false),
+        ["end", "of", "month"] | ["eom"] => {
+            let first = today.with_day(1)?;
+            first.checked_add_months(Months::new(1))?.pred_opt()?
+        }
+        ["end", "of", "year"] | ["eoy"] => NaiveDate::from_ymd_opt(today.year(), 12, 31)?,
+        [date] if date.contains('/') => parse_slash_date(date, today)?,
+        _ => return None,
+    };
+    Some(Deadline { date, time })
+}
+
+//从today开始往后找到第一个指定的星期几
+fn weekday_after(today: NaiveDate, weekday: Weekday, exclude_today: bool) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let days = if days == 0 && exclude_today { 7 } else { days };
+    today + Days::new(days as u64)
+}
+
+//支持MM/DD和MM/DD/YYYY，未指定年份时取最近的一个未来日期
+fn parse_slash_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let parts = input
+        .split('/')
+        .map(|part| part.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts.as_slice() {
+        [month, day] => (today.year()..=today.year() + 4)
+            .filter_map(|year| NaiveDate::from_ymd_opt(year, *month, *day))
+            .find(|date| *date >= today),
+        [month, day, year] => NaiveDate::from_ymd_opt(*year as i32, *month, *day),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2025-01-15 是星期三
+    const TODAY: &str = "2025-01-15";
+
+    fn parse(input: &str) -> Option<String> {
+        parse_relative(input, TODAY.parse().unwrap()).map(|deadline| deadline.to_string())
+    }
+
+    #[test]
+    fn keyword_test() {
+        assert_eq!(parse("today"), Some("2025-01-15".to_string()));
+        assert_eq!(parse("Tomorrow"), Some("2025-01-16".to_string()));
+        assert_eq!(parse("tmr"), Some("2025-01-16".to_string()));
+    }
+
+    #[test]
+    fn weekday_test() {
+        assert_eq!(parse("next fri"), Some("2025-01-17".to_string()));
+        assert_eq!(parse("next wed"), Some("2025-01-22".to_string()));
+        assert_eq!(parse("this wed"), Some("2025-01-15".to_string()));
+        assert_eq!(parse("next monday"), Some("2025-01-20".to_string()));
+        assert_eq!(parse("next funday"), None);
+    }
+
+    #[test]
+    fn offset_test() {
+        assert_eq!(parse("in 3 days"), Some("2025-01-18".to_string()));
+        assert_eq!(parse("in 1 day"), Some("2025-01-16".to_string()));
+        assert_eq!(parse("in 2 weeks"), Some("2025-01-29".to_string()));
+        assert_eq!(parse("in 1 month"), Some("2025-02-15".to_string()));
+        assert_eq!(parse("in 1 year"), Some("2026-01-15".to_string()));
+        assert_eq!(parse("next week"), Some("2025-01-22".to_string()));
+        assert_eq!(parse("next month"), Some("2025-02-15".to_string()));
+        assert_eq!(parse("in 3 fortnights"), None);
+        assert_eq!(parse("in some days"), None);
+        assert_eq!(parse("in 400000000 years"), None);
+    }
+
+    #[test]
+    fn end_of_period_test() {
+        assert_eq!(parse("end of month"), Some("2025-01-31".to_string()));
+        assert_eq!(parse("eom"), Some("2025-01-31".to_string()));
+        assert_eq!(parse("end of week"), Some("2025-01-19".to_string()));
+        assert_eq!(parse("end of year"), Some("2025-12-31".to_string()));
+        assert_eq!(
+            parse_relative("end of month", "2024-02-10".parse().unwrap())
+                .map(|deadline| deadline.date),
+            "2024-02-29".parse().ok()
+        );
+    }
+
+    #[test]
+    fn slash_date_test() {
+        assert_eq!(parse("12/25"), Some("2025-12-25".to_string()));
+        assert_eq!(parse("1/1"), Some("2026-01-01".to_string()));
+        assert_eq!(parse("2/29"), Some("2028-02-29".to_string()));
+        assert_eq!(parse("12/25/2026"), Some("2026-12-25".to_string()));
+        assert_eq!(parse("13/25"), None);
+    }
+
+    #[test]
+    fn time_test() {
+        assert_eq!(
+            parse("tomorrow 14:30"),
+            Some("2025-01-16 14:30".to_string())
+        );
+        assert_eq!(
+            parse("next fri at 9:05"),
+            Some("2025-01-17 09:05".to_string())
+        );
+        assert_eq!(parse("tomorrow 25:00"), None);
+    }
+}
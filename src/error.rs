@@ -0,0 +1,79 @@
+use std::{fmt, io, path::PathBuf};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+//输入解析失败（todo::ParseError）只显示在输入框下方，不会中断任何操作，所以不放在这里
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    //同步时服务器的回复不符合约定
+    Protocol(String),
+    //读写数据文件失败，记录是哪个文件
+    Storage(PathBuf, Box<Error>),
+}
+
+impl Error {
+    pub fn storage(path: impl Into<PathBuf>, err: impl Into<Error>) -> Self {
+        Error::Storage(path.into(), Box::new(err.into()))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Json(err) => write!(f, "invalid data: {}", err),
+            Error::Protocol(msg) => write!(f, "unexpected reply from server: {}", msg),
+            Error::Storage(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Json(err) => Some(err),
+            Error::Protocol(_) => None,
+            Error::Storage(_, err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn error_test() {
+        let json = serde_json::from_str::<Vec<u8>>("[1,").unwrap_err();
+        let err = Error::storage("/data/todo_list.json", json);
+        assert!(err
+            .to_string()
+            .starts_with("/data/todo_list.json: invalid data: EOF"));
+        assert!(matches!(
+            err.source().unwrap().downcast_ref::<Error>(),
+            Some(Error::Json(_))
+        ));
+        let err = Error::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(err.to_string(), "gone");
+        assert_eq!(
+            Error::Protocol("missing separator".to_string()).to_string(),
+            "unexpected reply from server: missing separator"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crossterm::event::{self, Event};
+use std::{io, time::Duration};
+
+#[cfg(test)]
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+#[cfg(test)]
+use std::collections::VecDeque;
+
+//事件来源，正常运行时读取终端，测试时按脚本依次给出按键
+pub trait EventSource {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct ScriptedEvents(pub VecDeque<Event>);
+
+#[cfg(test)]
+impl ScriptedEvents {
+    //普通字符原样输入，<enter>、<esc>、<c-a>这类写法表示特殊按键
+    pub fn new(script: &str) -> Self {
+        let mut events = VecDeque::new();
+        let mut rest = script;
+        while let Some(c) = rest.chars().next() {
+            let (key, len) = match rest.find('>').filter(|_| c == '<') {
+                Some(end) => (Self::special(&rest[1..end]), end + 1),
+                None => (KeyEvent::from(KeyCode::Char(c)), c.len_utf8()),
+            };
+            events.push_back(Event::Key(key));
+            rest = &rest[len..];
+        }
+        ScriptedEvents(events)
+    }
+
+    fn special(name: &str) -> KeyEvent {
+        if let Some(c) = name.strip_prefix("c-").and_then(|key| key.chars().next()) {
+            return KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        }
+        KeyEvent::from(match name {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Char(' '),
+            "bs" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "lt" => KeyCode::Char('<'),
+            _ => panic!("unknown key <{}>", name),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, _timeout: Duration) -> io::Result<bool> {
+        Ok(!self.0.is_empty())
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        self.0
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "script finished"))
+    }
+}
@@ -0,0 +1,466 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::app::{FilterType, InputMode, Message, Reorder, View};
+
+//视图放到v开头的组合键里，大写锁定时不会挡住小写字母的动作
+const DEFAULT_PRESET: [(&str, &str); 41] = [
+    ("down", "select_next"),
+    ("up", "select_previous"),
+    ("home", "select_first"),
+    ("end", "select_last"),
+    ("ctrl+up", "move_up"),
+    ("ctrl+down", "move_down"),
+    ("ctrl+home", "move_top"),
+    ("ctrl+end", "move_bottom"),
+    ("enter", "insert"),
+    ("d", "delete"),
+    ("x", "done"),
+    ("space", "mark"),
+    ("ctrl+a", "mark_all"),
+    ("esc", "clear_marks"),
+    ("r", "rewrite"),
+    ("ctrl+z", "undo"),
+    ("/", "search"),
+    (":", "palette"),
+    ("+", "progress_up"),
+    ("=", "progress_up"),
+    ("-", "progress_down"),
+    ("s", "sync"),
+    ("q", "quit"),
+    ("?", "help"),
+    ("L", "log"),
+    ("vc", "calendar"),
+    ("va", "agenda"),
+    ("vs", "stats"),
+    ("w", "filter_week"),
+    ("m", "filter_month"),
+    ("o", "filter_once"),
+    ("c", "filter_recur"),
+    ("p", "filter_progress"),
+    ("g", "filter_general"),
+    ("i", "filter_in_progress"),
+    ("u", "filter_upcoming"),
+    ("e", "filter_expired"),
+    ("n", "filter_no_deadline"),
+    ("a", "filter_all"),
+    ("ctrl+c", "quit"),
+    ("ctrl+s", "sync"),
+];
+
+//vim预设下过滤统一放到f开头的组合键里，避免和gg、dd冲突
+const VIM_PRESET: [(&str, &str); 47] = [
+    ("j", "select_next"),
+    ("down", "select_next"),
+    ("k", "select_previous"),
+    ("up", "select_previous"),
+    ("gg", "select_first"),
+    ("G", "select_last"),
+    ("K", "move_up"),
+    ("J", "move_down"),
+    ("ctrl+up", "move_up"),
+    ("ctrl+down", "move_down"),
+    ("ctrl+home", "move_top"),
+    ("ctrl+end", "move_bottom"),
+    ("i", "insert"),
+    ("enter", "insert"),
+    ("dd", "delete"),
+    ("x", "done"),
+    ("space", "mark"),
+    ("ctrl+a", "mark_all"),
+    ("esc", "clear_marks"),
+    ("r", "rewrite"),
+    ("u", "undo"),
+    ("/", "search"),
+    (":", "palette"),
+    ("+", "progress_up"),
+    ("=", "progress_up"),
+    ("-", "progress_down"),
+    ("s", "sync"),
+    ("q", "quit"),
+    ("?", "help"),
+    ("L", "log"),
+    ("vc", "calendar"),
+    ("va", "agenda"),
+    ("vs", "stats"),
+    ("fw", "filter_week"),
+    ("fm", "filter_month"),
+    ("fo", "filter_once"),
+    ("fc", "filter_recur"),
+    ("fp", "filter_progress"),
+    ("fg", "filter_general"),
+    ("fi", "filter_in_progress"),
+    ("fu", "filter_upcoming"),
+    ("fe", "filter_expired"),
+    ("fn", "filter_no_deadline"),
+    ("fa", "filter_all"),
+    ("home", "select_first"),
+    ("end", "select_last"),
+    ("ctrl+c", "quit"),
+];
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Vim,
+}
+
+//bindings中的按键会覆盖预设，动作写成空字符串表示解绑
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct KeymapConfig {
+    pub preset: KeymapPreset,
+    pub bindings: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    //字符本身已经区分了大小写，所以去掉shift
+    pub fn from_event(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Key {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    fn parse(input: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = input;
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = key;
+        }
+        let code = match rest.to_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" => KeyCode::Delete,
+            "backspace" => KeyCode::Backspace,
+            name => match (name.strip_prefix('f'), rest.chars().count()) {
+                (Some(n), _) if n.parse::<u8>().is_ok() => KeyCode::F(n.parse().ok()?),
+                (_, 1) => KeyCode::Char(rest.chars().next()?),
+                _ => return None,
+            },
+        };
+        Some(Key { code, modifiers }.normalize())
+    }
+
+    fn normalize(mut self) -> Self {
+        if let KeyCode::Char(_) = self.code {
+            self.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            code => code.to_string().to_lowercase().replace(' ', ""),
+        };
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            format!("C-{}", key)
+        } else if self.modifiers.contains(KeyModifiers::ALT) {
+            format!("M-{}", key)
+        } else {
+            key
+        }
+    }
+}
+
+//"gg"这类多个字符的写法表示依次按下的组合键，也可以用空格分隔
+fn parse_keys(input: &str) -> Option<Vec<Key>> {
+    let tokens = input.split_whitespace().collect::<Vec<_>>();
+    if tokens.len() > 1 {
+        return tokens.into_iter().map(Key::parse).collect();
+    }
+    if let Some(key) = Key::parse(input) {
+        return Some(vec![key]);
+    }
+    if input.contains('+') {
+        return None;
+    }
+    input.chars().map(|c| Key::parse(&c.to_string())).collect()
+}
+
+pub(crate) fn action(name: &str) -> Option<Message> {
+    let msg = match name {
+        "quit" => Message::Quit,
+        "delete" => Message::Delete,
+        "done" => Message::Done,
+        "mark" => Message::Mark,
+        "mark_all" => Message::MarkAll,
+        "clear_marks" => Message::ClearMarks,
+        "sync" => Message::Sync,
+        "rewrite" => Message::Rewrite,
+        "insert" => Message::InputModeChange(InputMode::Insert),
+        "search" => Message::InputModeChange(InputMode::Search),
+        "palette" => Message::InputModeChange(InputMode::Command),
+        "select_next" => Message::SelectNext,
+        "select_previous" => Message::SelectPrevious,
+        "select_first" => Message::SelectFirst,
+        "select_last" => Message::SelectLast,
+        "move_up" => Message::Reorder(Reorder::Up),
+        "move_down" => Message::Reorder(Reorder::Down),
+        "move_top" => Message::Reorder(Reorder::Top),
+        "move_bottom" => Message::Reorder(Reorder::Bottom),
+        "progress_up" => Message::StepProgress(true),
+        "progress_down" => Message::StepProgress(false),
+        "undo" => Message::Undo,
+        "help" => Message::ToggleHelp,
+        "log" => Message::ToggleLog,
+        "calendar" => Message::SwitchView(View::Calendar),
+        "agenda" => Message::SwitchView(View::Agenda),
+        "stats" => Message::SwitchView(View::Stats),
+        "filter_all" => Message::Filter(FilterType::All),
+        "filter_expired" => Message::Filter(FilterType::Expired),
+        "filter_in_progress" => Message::Filter(FilterType::InProgress),
+        "filter_no_deadline" => Message::Filter(FilterType::NoDeadline),
+        "filter_upcoming" => Message::Filter(FilterType::UpComing),
+        "filter_week" => Message::Filter(FilterType::Week),
+        "filter_month" => Message::Filter(FilterType::Month),
+        "filter_once" => Message::Filter(FilterType::Once),
+        "filter_recur" => Message::Filter(FilterType::Recur),
+        "filter_progress" => Message::Filter(FilterType::Progress),
+        "filter_general" => Message::Filter(FilterType::General),
+        _ => return None,
+    };
+    Some(msg)
+}
+
+//底部帮助栏中显示的名称，同名的动作合并显示
+fn label(name: &str) -> &str {
+    match name {
+        "select_next" => "Next",
+        "select_previous" => "Previous",
+        "select_first" => "First",
+        "select_last" => "Last",
+        "insert" => "Insert",
+        "delete" => "Delete",
+        "done" => "Done",
+        "mark" | "mark_all" | "clear_marks" => "Mark",
+        "rewrite" => "Rewrite",
+        "undo" => "Undo",
+        "search" => "Search",
+        "palette" => "Command",
+        "progress_up" | "progress_down" => "Progress",
+        "move_up" | "move_down" | "move_top" | "move_bottom" => "Move",
+        "sync" => "Sync",
+        "quit" => "Quit",
+        "help" => "Help",
+        "log" => "Log",
+        "calendar" => "Calendar",
+        "agenda" => "Agenda",
+        "stats" => "Stats",
+        name if name.starts_with("filter_") => "Filter",
+        name => name,
+    }
+}
+
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, String)>,
+    pending: Vec<Key>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeymapConfig::default())
+    }
+}
+
+impl Keymap {
+    pub fn new(config: &KeymapConfig) -> Self {
+        let preset = match config.preset {
+            KeymapPreset::Default => &DEFAULT_PRESET[..],
+            KeymapPreset::Vim => &VIM_PRESET[..],
+        };
+        let mut bindings = preset
+            .iter()
+            .filter_map(|(keys, name)| Some((parse_keys(keys)?, name.to_string())))
+            .collect::<Vec<_>>();
+        for (keys, name) in config.bindings.iter() {
+            let Some(keys) = parse_keys(keys) else {
+                continue;
+            };
+            bindings.retain(|(bound, _)| *bound != keys);
+            if action(name).is_some() {
+                bindings.push((keys, name.clone()));
+            }
+        }
+        Keymap {
+            bindings,
+            pending: vec![],
+        }
+    }
+
+    pub(crate) fn handle(&mut self, event: KeyEvent) -> Option<Message> {
+        let key = Key::from_event(event);
+        self.pending.push(key);
+        if let Some(name) = self.find(&self.pending) {
+            self.pending.clear();
+            return action(&name);
+        }
+        if self
+            .bindings
+            .iter()
+            .any(|(keys, _)| keys.starts_with(&self.pending))
+        {
+            return None;
+        }
+        //不是任何组合键的前缀，丢弃之前的按键只匹配当前这一个
+        let retry = self.pending.len() > 1;
+        self.pending.clear();
+        if retry {
+            return self.handle(event);
+        }
+        //大写没有单独绑定时按小写处理，避免开着大写锁定时按键失效
+        match key.code {
+            KeyCode::Char(c) if c.is_uppercase() => {
+                let lower = Key {
+                    code: KeyCode::Char(c.to_ascii_lowercase()),
+                    ..key
+                };
+                self.find(&[lower]).and_then(|name| action(&name))
+            }
+            _ => None,
+        }
+    }
+
+    fn find(&self, keys: &[Key]) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == keys)
+            .map(|(_, name)| name.clone())
+    }
+
+    //按动作名称分组生成帮助栏内容，顺序和绑定顺序一致
+    pub fn help(&self) -> Vec<(String, String)> {
+        let mut help: Vec<(String, Vec<String>)> = vec![];
+        for (keys, name) in self.bindings.iter() {
+            let keys = keys.iter().map(Key::label).collect::<String>();
+            let label = label(name);
+            match help.iter_mut().find(|(bound, _)| bound == label) {
+                Some((_, bound_keys)) => bound_keys.push(keys),
+                None => help.push((label.to_string(), vec![keys])),
+            }
+        }
+        help.into_iter()
+            .map(|(label, keys)| (label, keys.join("/")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keymap: &mut Keymap, c: char) -> Option<String> {
+        let modifiers = if c.is_uppercase() {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        keymap
+            .handle(KeyEvent::new(KeyCode::Char(c), modifiers))
+            .map(|msg| format!("{:?}", msg))
+    }
+
+    #[test]
+    fn key_parse_test() {
+        assert_eq!(
+            parse_keys("ctrl+z"),
+            Some(vec![Key {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL
+            }])
+        );
+        assert_eq!(parse_keys("gg").map(|keys| keys.len()), Some(2));
+        assert_eq!(parse_keys("g g").map(|keys| keys.len()), Some(2));
+        assert_eq!(parse_keys("+").map(|keys| keys.len()), Some(1));
+        assert_eq!(
+            parse_keys("f5").map(|keys| keys[0].code),
+            Some(KeyCode::F(5))
+        );
+        assert_eq!(
+            parse_keys("G").map(|keys| keys[0].label()),
+            Some("G".to_string())
+        );
+        assert_eq!(parse_keys("ctrl+foo"), None);
+    }
+
+    #[test]
+    fn vim_chord_test() {
+        let mut keymap = Keymap::new(&KeymapConfig {
+            preset: KeymapPreset::Vim,
+            ..Default::default()
+        });
+        assert_eq!(press(&mut keymap, 'g'), None);
+        assert_eq!(press(&mut keymap, 'g').as_deref(), Some("SelectFirst"));
+        assert_eq!(press(&mut keymap, 'G').as_deref(), Some("SelectLast"));
+        assert_eq!(press(&mut keymap, 'd'), None);
+        assert_eq!(press(&mut keymap, 'j').as_deref(), Some("SelectNext"));
+        assert_eq!(press(&mut keymap, 'd'), None);
+        assert_eq!(press(&mut keymap, 'd').as_deref(), Some("Delete"));
+        assert_eq!(press(&mut keymap, 'f'), None);
+        assert_eq!(press(&mut keymap, 'w').as_deref(), Some("Filter(Week)"));
+    }
+
+    #[test]
+    fn caps_and_override_test() {
+        let mut bindings = BTreeMap::new();
+        bindings.insert("x".to_string(), "delete".to_string());
+        bindings.insert("d".to_string(), "".to_string());
+        let mut keymap = Keymap::new(&KeymapConfig {
+            bindings,
+            ..Default::default()
+        });
+        assert_eq!(press(&mut keymap, 'Q').as_deref(), Some("Quit"));
+        //大写锁定时小写按键的动作不会被视图按键挡住
+        assert_eq!(press(&mut keymap, 'C').as_deref(), Some("Filter(Recur)"));
+        assert_eq!(press(&mut keymap, 'A').as_deref(), Some("Filter(All)"));
+        assert_eq!(press(&mut keymap, 'S').as_deref(), Some("Sync"));
+        assert_eq!(press(&mut keymap, 'v'), None);
+        assert_eq!(
+            press(&mut keymap, 'c').as_deref(),
+            Some("SwitchView(Calendar)")
+        );
+        assert_eq!(press(&mut keymap, 'x').as_deref(), Some("Delete"));
+        assert_eq!(press(&mut keymap, 'd'), None);
+        let help = keymap.help();
+        assert!(help.contains(&("Delete".to_string(), "x".to_string())));
+        assert!(help.contains(&("Progress".to_string(), "+/=/-".to_string())));
+    }
+}
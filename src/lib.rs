@@ -1,9 +1,27 @@
+mod agenda;
 mod app;
+mod calendar;
+mod completion;
+mod config;
+mod crash;
+mod date;
+mod error;
+mod events;
+mod keymap;
+mod notice;
+mod palette;
+mod recur;
+mod reminder;
+mod stats;
 mod sync;
+mod theme;
 mod todo;
 
 pub use crate::{
+    agenda::print_agenda,
     app::{App, CURRENT_PATH},
-    sync::{sync_app_data, SyncAction, SyncState},
+    crash::install_panic_hook,
+    error::{Error, Result},
+    sync::{sync_app_data, SyncAction, SyncState, SyncStatus},
     todo::Todo,
 };
@@ -1,7 +1,34 @@
-use ttd_v2::App;
-fn main() -> std::io::Result<()> {
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use std::sync::Arc;
+use ttd_v2::{App, CURRENT_PATH};
+fn main() {
+    //ttd-v2 agenda [days] 直接输出日程，不进入界面
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = if args.first().map(String::as_str) == Some("agenda") {
+        ttd_v2::print_agenda(args.get(1).and_then(|days| days.parse().ok()))
+    } else {
+        run_tui()
+    };
+    //终端已经恢复，直接输出可读的错误信息
+    if let Err(err) = result {
+        eprintln!("{}: {}", env!("CARGO_PKG_NAME"), err);
+        std::process::exit(1);
+    }
+}
+
+fn run_tui() -> ttd_v2::Result<()> {
     let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
+    let mut app = App::default();
+    //在ratatui的panic hook之后安装，崩溃时先执行
+    ttd_v2::install_panic_hook(
+        CURRENT_PATH.to_path_buf(),
+        Arc::clone(&app.todo_list),
+        Arc::clone(&app.notices),
+    );
+    let app_result = crossterm::execute!(std::io::stdout(), EnableMouseCapture)
+        .map_err(ttd_v2::Error::from)
+        .and_then(|_| app.run(&mut terminal));
+    let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
     app_result
 }
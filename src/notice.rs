@@ -0,0 +1,106 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use std::sync::{Arc, RwLock, TryLockError};
+
+//状态栏中的消息显示几秒后消失，完整记录在消息日志中
+const TRANSIENT_SECS: i64 = 5;
+const MAX_NOTICES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn print_info(&self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notice {
+    pub at: NaiveDateTime,
+    pub level: Level,
+    pub text: String,
+}
+
+//后台同步线程也会写入，所以和todo_list一样共享
+pub type NoticeLog = Arc<RwLock<Vec<Notice>>>;
+
+pub fn push(log: &NoticeLog, level: Level, text: impl Into<String>) {
+    push_at(log, chrono::Local::now().naive_local(), level, text);
+}
+
+fn push_at(log: &NoticeLog, at: NaiveDateTime, level: Level, text: impl Into<String>) {
+    append(&mut log.write().unwrap(), at, level, text);
+}
+
+//崩溃处理中使用，锁被占用时放弃，锁中毒时照常写入，不会阻塞也不会再次panic
+pub fn try_push(log: &NoticeLog, level: Level, text: impl Into<String>) -> bool {
+    let mut log = match log.try_write() {
+        Ok(log) => log,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return false,
+    };
+    append(&mut log, chrono::Local::now().naive_local(), level, text);
+    true
+}
+
+fn append(log: &mut Vec<Notice>, at: NaiveDateTime, level: Level, text: impl Into<String>) {
+    log.push(Notice {
+        at,
+        level,
+        text: text.into(),
+    });
+    if log.len() > MAX_NOTICES {
+        log.remove(0);
+    }
+}
+
+//最近一条消息还没过期时返回它
+pub fn latest(log: &NoticeLog, now: NaiveDateTime) -> Option<Notice> {
+    log.read()
+        .unwrap()
+        .last()
+        .filter(|notice| now - notice.at < TimeDelta::seconds(TRANSIENT_SECS))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notice_test() {
+        let at = |input: &str| NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S").unwrap();
+        let log = NoticeLog::default();
+        assert_eq!(latest(&log, at("2025-01-01 12:00:00")), None);
+        push_at(&log, at("2025-01-01 12:00:00"), Level::Info, "Sync started");
+        push_at(
+            &log,
+            at("2025-01-01 12:00:05"),
+            Level::Warn,
+            "No server found",
+        );
+        let notice = latest(&log, at("2025-01-01 12:00:09")).unwrap();
+        assert_eq!(notice.text, "No server found");
+        assert_eq!(latest(&log, at("2025-01-01 12:00:10")), None);
+
+        for index in 0..MAX_NOTICES {
+            push_at(
+                &log,
+                at("2025-01-01 12:01:00"),
+                Level::Info,
+                index.to_string(),
+            );
+        }
+        let log = log.read().unwrap();
+        assert_eq!(log.len(), MAX_NOTICES);
+        assert_eq!(log[0].text, "0");
+    }
+}
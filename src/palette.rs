@@ -0,0 +1,294 @@
+use crate::app::{FilterType, Message, SortKey};
+
+pub struct Command {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub description: &'static str,
+}
+
+pub const COMMANDS: [Command; 20] = [
+    Command {
+        name: "add",
+        args: "<text>",
+        description: "Add a todo, same syntax as the input box",
+    },
+    Command {
+        name: "delete",
+        args: "",
+        description: "Delete the selected todo",
+    },
+    Command {
+        name: "done",
+        args: "",
+        description: "Mark the selected todo as done",
+    },
+    Command {
+        name: "rewrite",
+        args: "",
+        description: "Edit the selected todo",
+    },
+    Command {
+        name: "tag",
+        args: "<#tag>",
+        description: "Add a tag to the marked or selected todos",
+    },
+    Command {
+        name: "untag",
+        args: "<#tag>",
+        description: "Remove a tag from the marked or selected todos",
+    },
+    Command {
+        name: "move",
+        args: "[list]",
+        description: "Move the marked or selected todos to another list",
+    },
+    Command {
+        name: "kind",
+        args: "<-date|@progress|general>",
+        description: "Change the kind of the marked or selected todos",
+    },
+    Command {
+        name: "undo",
+        args: "",
+        description: "Undo the last change",
+    },
+    Command {
+        name: "progress",
+        args: "<up|down>",
+        description: "Step the progress of the selected todo",
+    },
+    Command {
+        name: "filter",
+        args: "<kind|state>",
+        description: "Only show todos of a kind or state",
+    },
+    Command {
+        name: "search",
+        args: "<text>",
+        description: "Only show todos containing the text",
+    },
+    Command {
+        name: "sort",
+        args: "<field>",
+        description: "Sort the table by a field, manual for the saved order",
+    },
+    Command {
+        name: "list",
+        args: "[name]",
+        description: "Switch to another list, empty for the default list",
+    },
+    Command {
+        name: "export",
+        args: "<path>",
+        description: "Export todos to a .json or plain text file",
+    },
+    Command {
+        name: "sync",
+        args: "",
+        description: "Sync with the server",
+    },
+    Command {
+        name: "save",
+        args: "",
+        description: "Save todos to disk",
+    },
+    Command {
+        name: "help",
+        args: "",
+        description: "Show keys and input syntax",
+    },
+    Command {
+        name: "log",
+        args: "",
+        description: "Show recent messages and errors",
+    },
+    Command {
+        name: "quit",
+        args: "",
+        description: "Save and quit",
+    },
+];
+
+const FILTERS: [(&str, FilterType); 11] = [
+    ("all", FilterType::All),
+    ("general", FilterType::General),
+    ("week", FilterType::Week),
+    ("month", FilterType::Month),
+    ("once", FilterType::Once),
+    ("recur", FilterType::Recur),
+    ("progress", FilterType::Progress),
+    ("expired", FilterType::Expired),
+    ("in_progress", FilterType::InProgress),
+    ("upcoming", FilterType::UpComing),
+    ("no_deadline", FilterType::NoDeadline),
+];
+
+const SORT_KEYS: [(&str, SortKey); 6] = [
+    ("manual", SortKey::Manual),
+    ("text", SortKey::Text),
+    ("kind", SortKey::Kind),
+    ("state", SortKey::State),
+    ("next", SortKey::Next),
+    ("created", SortKey::Created),
+];
+
+//模糊匹配：pattern中的字符按顺序出现在candidate中即可，连续匹配和开头匹配得分更高
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last_match = None;
+    for c in pattern.to_lowercase().chars() {
+        let index = candidate[position..].iter().position(|&other| other == c)? + position;
+        score += match last_match {
+            Some(last) if last + 1 == index => 5,
+            None if index == 0 => 10,
+            _ => 1,
+        };
+        last_match = Some(index);
+        position = index + 1;
+    }
+    Some(score - candidate.len() as i32 / 4)
+}
+
+//根据已输入的内容给出候选项：第一个词匹配命令，之后匹配参数
+pub fn suggestions(input: &str, lists: &[String]) -> Vec<(String, String)> {
+    let input = input.trim_start();
+    let mut scored = match input.split_once(' ') {
+        None => COMMANDS
+            .iter()
+            .filter_map(|command| {
+                let score = fuzzy_score(input, command.name)?;
+                let replacement = if command.args.is_empty() {
+                    command.name.to_string()
+                } else {
+                    format!("{} ", command.name)
+                };
+                let description =
+                    format!("{} {}  {}", command.name, command.args, command.description);
+                Some((score, replacement, description))
+            })
+            .collect::<Vec<_>>(),
+        Some((name, arg)) => {
+            let args = match name {
+                "filter" => FILTERS.iter().map(|(name, _)| name.to_string()).collect(),
+                "sort" => SORT_KEYS.iter().map(|(name, _)| name.to_string()).collect(),
+                "progress" => vec!["up".to_string(), "down".to_string()],
+                "list" | "move" => lists.to_vec(),
+                _ => vec![],
+            };
+            args.into_iter()
+                .filter_map(|candidate| {
+                    let score = fuzzy_score(arg.trim(), &candidate)?;
+                    Some((score, format!("{} {}", name, candidate), candidate))
+                })
+                .collect()
+        }
+    };
+    scored.sort_by_key(|(score, ..)| -score);
+    scored
+        .into_iter()
+        .map(|(_, replacement, description)| (replacement, description))
+        .collect()
+}
+
+pub(crate) fn parse(input: &str) -> Result<Message, String> {
+    let input = input.trim();
+    let (name, arg) = input.split_once(' ').unwrap_or((input, ""));
+    let arg = arg.trim();
+    let require_arg = |usage: &str| {
+        if arg.is_empty() {
+            Err(format!("usage: {} {}", name, usage))
+        } else {
+            Ok(arg.to_string())
+        }
+    };
+    let msg = match name {
+        "add" => Message::AddText(require_arg("<text>")?),
+        "delete" => Message::Delete,
+        "done" => Message::Done,
+        "rewrite" => Message::Rewrite,
+        "tag" => Message::Tag(require_arg("<#tag>")?, true),
+        "untag" => Message::Tag(require_arg("<#tag>")?, false),
+        "move" => Message::MoveTo(arg.to_string()),
+        "kind" => Message::ChangeKind(require_arg("<-date|@progress|general>")?),
+        "undo" => Message::Undo,
+        "progress" => match arg {
+            "up" | "+" => Message::StepProgress(true),
+            "down" | "-" => Message::StepProgress(false),
+            _ => return Err("usage: progress <up|down>".to_string()),
+        },
+        "filter" => {
+            let (_, filter_type) = FILTERS
+                .iter()
+                .find(|(name, _)| *name == arg)
+                .ok_or_else(|| format!("unknown filter \"{}\"", arg))?;
+            Message::Filter(filter_type.clone())
+        }
+        "search" => Message::Filter(FilterType::Search(require_arg("<text>")?)),
+        "sort" => {
+            let (_, sort_key) = SORT_KEYS
+                .iter()
+                .find(|(name, _)| *name == arg)
+                .ok_or_else(|| format!("unknown sort field \"{}\"", arg))?;
+            Message::Sort(*sort_key)
+        }
+        "list" => Message::SwitchList(arg.to_string()),
+        "export" => Message::Export(require_arg("<path>")?),
+        "sync" => Message::Sync,
+        "save" => Message::Save,
+        "help" => Message::ToggleHelp,
+        "log" => Message::ToggleLog,
+        "quit" => Message::Quit,
+        _ => return Err(format!("unknown command \"{}\"", name)),
+    };
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_test() {
+        assert!(fuzzy_score("flt", "filter").is_some());
+        assert!(fuzzy_score("ftl", "filter").is_none());
+        assert!(fuzzy_score("sy", "sync") > fuzzy_score("sy", "progress_sync"));
+        let lists = vec!["work".to_string(), "home".to_string()];
+        assert_eq!(suggestions("del", &lists)[0].0, "delete");
+        assert_eq!(suggestions("flt", &lists)[0].0, "filter ");
+        assert_eq!(suggestions("list w", &lists)[0].0, "list work");
+        assert_eq!(suggestions("move h", &lists)[0].0, "move home");
+        assert_eq!(suggestions("filter inp", &lists)[0].0, "filter in_progress");
+    }
+
+    #[test]
+    fn parse_test() {
+        assert!(matches!(
+            parse("sort next"),
+            Ok(Message::Sort(SortKey::Next))
+        ));
+        assert!(matches!(
+            parse("filter expired"),
+            Ok(Message::Filter(FilterType::Expired))
+        ));
+        assert!(matches!(
+            parse("add buy milk -tomorrow"),
+            Ok(Message::AddText(text)) if text == "buy milk -tomorrow"
+        ));
+        assert!(matches!(parse("list"), Ok(Message::SwitchList(name)) if name.is_empty()));
+        assert!(matches!(
+            parse("untag #home"),
+            Ok(Message::Tag(tag, false)) if tag == "#home"
+        ));
+        assert!(matches!(parse("move work"), Ok(Message::MoveTo(list)) if list == "work"));
+        assert_eq!(
+            parse("export").unwrap_err(),
+            "usage: export <path>".to_string()
+        );
+        assert_eq!(
+            parse("frobnicate").unwrap_err(),
+            "unknown command \"frobnicate\"".to_string()
+        );
+    }
+}
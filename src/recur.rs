@@ -0,0 +1,320 @@
+use chrono::{Datelike, Month, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+const WEEKENDS: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+const NTH: [(&str, i8); 10] = [
+    ("first", 1),
+    ("1st", 1),
+    ("second", 2),
+    ("2nd", 2),
+    ("third", 3),
+    ("3rd", 3),
+    ("fourth", 4),
+    ("4th", 4),
+    ("last", -1),
+    ("fifth", 5),
+];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum RecurRule {
+    Daily(u32),
+    Weekly(u32, Vec<Weekday>),
+    //第几个星期几，-1表示最后一个
+    MonthlyNth(i8, Weekday),
+    Yearly(u32, u32),
+}
+
+//从文件读取时校验规则，手动修改或同步来的非法值不会进入occurs_on和Display
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(try_from = "RawRecurrence")]
+pub struct Recurrence {
+    pub rule: RecurRule,
+    pub start: NaiveDate,
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+struct RawRecurrence {
+    rule: RecurRule,
+    start: NaiveDate,
+    until: Option<NaiveDate>,
+}
+
+impl TryFrom<RawRecurrence> for Recurrence {
+    type Error = String;
+
+    fn try_from(raw: RawRecurrence) -> Result<Self, Self::Error> {
+        let valid = match raw.rule {
+            RecurRule::Daily(interval) => interval > 0,
+            RecurRule::Weekly(interval, ref days) => interval > 0 && !days.is_empty(),
+            RecurRule::MonthlyNth(nth, _) => NTH.iter().any(|(_, n)| *n == nth),
+            RecurRule::Yearly(month, day) => NaiveDate::from_ymd_opt(2000, month, day).is_some(),
+        };
+        match valid {
+            true => Ok(Recurrence {
+                rule: raw.rule,
+                start: raw.start,
+                until: raw.until,
+            }),
+            false => Err(format!("invalid recurrence rule: {:?}", raw.rule)),
+        }
+    }
+}
+
+impl Recurrence {
+    //间隔类规则以start为起点计算，未指定from时以输入当天为起点
+    pub fn parse(input: &str, today: NaiveDate) -> Option<Self> {
+        let input = input.trim().to_lowercase().replace(',', " ");
+        let (body, until) = split_clause(&input, "until")?;
+        let (body, start) = split_clause(body, "from")?;
+        let start = start.unwrap_or(today);
+        let tokens = body
+            .split_whitespace()
+            .filter(|token| !["the", "of", "month", "on", "and"].contains(token))
+            .collect::<Vec<_>>();
+        let rule = match tokens.as_slice() {
+            ["daily"] | ["every", "day"] => RecurRule::Daily(1),
+            ["every", n, "days" | "day"] => RecurRule::Daily(parse_interval(n)?),
+            ["weekdays"] | ["every", "weekday"] => RecurRule::Weekly(1, WEEKDAYS.to_vec()),
+            ["weekends"] | ["every", "weekend"] => RecurRule::Weekly(1, WEEKENDS.to_vec()),
+            ["weekly", days @ ..] | ["every", "week", days @ ..] => {
+                RecurRule::Weekly(1, parse_weekdays(days, start)?)
+            }
+            ["every", n, "weeks" | "week", days @ ..] => {
+                RecurRule::Weekly(parse_interval(n)?, parse_weekdays(days, start)?)
+            }
+            ["every", days @ ..] if !days.is_empty() => {
+                RecurRule::Weekly(1, parse_weekdays(days, start)?)
+            }
+            [nth, weekday] => {
+                let (_, nth) = NTH.iter().find(|(word, _)| word == nth)?;
+                RecurRule::MonthlyNth(*nth, weekday.parse().ok()?)
+            }
+            ["yearly", month, day] | ["every", "year", month, day] => {
+                let month = month.parse::<Month>().ok()?.number_from_month();
+                let day = day.parse::<u32>().ok()?;
+                //用闰年校验日期，保证2月29日可以输入
+                NaiveDate::from_ymd_opt(2000, month, day)?;
+                RecurRule::Yearly(month, day)
+            }
+            _ => return None,
+        };
+        Some(Recurrence { rule, start, until })
+    }
+
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        if date < self.start || self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+        match self.rule {
+            RecurRule::Daily(interval) => (date - self.start).num_days() % interval as i64 == 0,
+            RecurRule::Weekly(interval, ref days) => {
+                let weeks = (week_start(date) - week_start(self.start)).num_weeks();
+                days.contains(&date.weekday()) && weeks % interval as i64 == 0
+            }
+            RecurRule::MonthlyNth(nth, weekday) => {
+                date.weekday() == weekday
+                    && if nth < 0 {
+                        date.checked_add_days(chrono::Days::new(7))
+                            .is_none_or(|next| next.month() != date.month())
+                    } else {
+                        (date.day0() / 7 + 1) as i8 == nth
+                    }
+            }
+            RecurRule::Yearly(month, day) => date.month() == month && date.day() == day,
+        }
+    }
+
+    pub fn next_occurrence(&self, from: NaiveDate) -> Option<NaiveDate> {
+        //最长的间隔是2月29日，8年内一定能找到
+        from.iter_days()
+            .take(366 * 8)
+            .take_while(|date| self.until.is_none_or(|until| *date <= until))
+            .find(|date| self.occurs_on(*date))
+    }
+
+    pub fn is_finished(&self, today: NaiveDate) -> bool {
+        self.next_occurrence(today).is_none()
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |days: &[Weekday]| {
+            days.iter()
+                .map(|day| day.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self.rule {
+            RecurRule::Daily(1) => write!(f, "daily")?,
+            RecurRule::Daily(interval) => write!(f, "every {} days", interval)?,
+            RecurRule::Weekly(1, ref days) if days[..] == WEEKDAYS => write!(f, "weekdays")?,
+            RecurRule::Weekly(1, ref days) if days[..] == WEEKENDS => write!(f, "weekends")?,
+            RecurRule::Weekly(1, ref days) => write!(f, "weekly on {}", join(days))?,
+            RecurRule::Weekly(interval, ref days) => {
+                write!(f, "every {} weeks on {}", interval, join(days))?
+            }
+            RecurRule::MonthlyNth(nth, weekday) => {
+                let (word, _) = NTH.iter().find(|(_, n)| *n == nth).unwrap();
+                write!(f, "{} {} of the month", word, weekday)?
+            }
+            RecurRule::Yearly(month, day) => {
+                let month = Month::try_from(month as u8).unwrap();
+                write!(f, "yearly on {} {}", &month.name()[..3], day)?
+            }
+        }
+        //只有带间隔的规则才依赖起始日期
+        if let RecurRule::Daily(2..) | RecurRule::Weekly(2.., _) = self.rule {
+            write!(f, " from {}", self.start)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, " until {}", until)?;
+        }
+        Ok(())
+    }
+}
+
+fn split_clause<'a>(input: &'a str, keyword: &str) -> Option<(&'a str, Option<NaiveDate>)> {
+    match input.split_once(&format!(" {} ", keyword)) {
+        Some((body, date)) => Some((body, Some(date.trim().parse().ok()?))),
+        None => Some((input, None)),
+    }
+}
+
+fn parse_interval(input: &str) -> Option<u32> {
+    input.parse::<u32>().ok().filter(|n| *n > 0)
+}
+
+fn parse_weekdays(input: &[&str], start: NaiveDate) -> Option<Vec<Weekday>> {
+    if input.is_empty() {
+        return Some(vec![start.weekday()]);
+    }
+    let mut days = input
+        .iter()
+        .map(|day| day.parse::<Weekday>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    days.sort_by_key(Weekday::num_days_from_monday);
+    days.dedup();
+    Some(days)
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(input: &str) -> NaiveDate {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn recurrence_parse_test() {
+        let today = date("2025-01-01");
+        let parse = |input: &str| Recurrence::parse(input, today).map(|r| r.rule);
+        assert_eq!(parse("every 3 days"), Some(RecurRule::Daily(3)));
+        assert_eq!(
+            parse("weekdays"),
+            Some(RecurRule::Weekly(1, WEEKDAYS.to_vec()))
+        );
+        assert_eq!(
+            parse("every 2 weeks"),
+            Some(RecurRule::Weekly(2, vec![Weekday::Wed]))
+        );
+        assert_eq!(
+            parse("every 2 weeks on fri, mon"),
+            Some(RecurRule::Weekly(2, vec![Weekday::Mon, Weekday::Fri]))
+        );
+        assert_eq!(
+            parse("first Monday of the month"),
+            Some(RecurRule::MonthlyNth(1, Weekday::Mon))
+        );
+        assert_eq!(
+            parse("last fri"),
+            Some(RecurRule::MonthlyNth(-1, Weekday::Fri))
+        );
+        assert_eq!(parse("yearly on Mar 15"), Some(RecurRule::Yearly(3, 15)));
+        assert_eq!(parse("yearly feb 30"), None);
+        assert_eq!(parse("every 0 days"), None);
+        let recurrence = Recurrence::parse("daily until 2025-02-01", today).unwrap();
+        assert_eq!(recurrence.until, Some(date("2025-02-01")));
+        assert_eq!(Recurrence::parse("daily until someday", today), None);
+    }
+
+    #[test]
+    fn recurrence_display_round_trip_test() {
+        let today = date("2025-01-01");
+        for input in [
+            "daily",
+            "every 3 days from 2024-12-30",
+            "weekdays",
+            "weekly on Tue,Thu",
+            "every 2 weeks on Mon from 2024-12-30 until 2025-06-01",
+            "last Fri of the month",
+            "yearly on Mar 15",
+        ] {
+            let recurrence = Recurrence::parse(input, today).unwrap();
+            assert_eq!(recurrence.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn recurrence_occurrence_test() {
+        let today = date("2025-01-01");
+        let every_2_weeks = Recurrence::parse("every 2 weeks on mon", today).unwrap();
+        assert!(!every_2_weeks.occurs_on(date("2025-01-06")));
+        assert!(every_2_weeks.occurs_on(date("2025-01-13")));
+        assert_eq!(
+            every_2_weeks.next_occurrence(date("2025-01-14")),
+            Some(date("2025-01-27"))
+        );
+        let first_monday = Recurrence::parse("first mon", today).unwrap();
+        assert_eq!(
+            first_monday.next_occurrence(today),
+            Some(date("2025-01-06"))
+        );
+        let last_friday = Recurrence::parse("last fri", today).unwrap();
+        assert_eq!(
+            last_friday.next_occurrence(date("2025-02-01")),
+            Some(date("2025-02-28"))
+        );
+        let leap_day = Recurrence::parse("yearly feb 29", today).unwrap();
+        assert_eq!(leap_day.next_occurrence(today), Some(date("2028-02-29")));
+        let every_3_days = Recurrence::parse("every 3 days until 2025-01-05", today).unwrap();
+        assert!(every_3_days.occurs_on(date("2025-01-04")));
+        assert!(every_3_days.is_finished(date("2025-01-05")));
+    }
+
+    #[test]
+    fn recurrence_deserialize_test() {
+        let load = |rule: &str| {
+            serde_json::from_str::<Recurrence>(&format!(
+                r#"{{"rule":{},"start":"2025-01-01","until":null}}"#,
+                rule
+            ))
+        };
+        let recurrence = load(r#"{"MonthlyNth":[-1,"Fri"]}"#).unwrap();
+        assert_eq!(recurrence.to_string(), "last Fri of the month");
+        for rule in [
+            r#"{"Daily":0}"#,
+            r#"{"Weekly":[0,["Mon"]]}"#,
+            r#"{"Weekly":[1,[]]}"#,
+            r#"{"MonthlyNth":[9,"Mon"]}"#,
+            r#"{"Yearly":[13,1]}"#,
+            r#"{"Yearly":[2,30]}"#,
+        ] {
+            assert!(load(rule).is_err(), "{}", rule);
+        }
+    }
+}
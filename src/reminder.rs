@@ -0,0 +1,24 @@
+use std::process::Command;
+
+use crate::todo::Todo;
+
+//提醒命令在后台执行，待办内容通过环境变量传给命令
+pub fn run_command(command: &str, todo: &Todo) {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    cmd.env("TTD_TODO_TEXT", &todo.text)
+        .env("TTD_TODO_DUE", todo.kind.print_info());
+    if let Ok(mut child) = cmd.spawn() {
+        std::thread::spawn(move || child.wait());
+    }
+}
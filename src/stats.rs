@@ -0,0 +1,207 @@
+use chrono::{Days, NaiveDate, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::todo::{MonthEnd, Todo, TodoKind, TodoState};
+
+//每完成一次记录一条，周期性的待办每次完成都会记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoneEntry {
+    pub id: String,
+    pub text: String,
+    pub kind: String,
+    pub due: Option<NaiveDate>,
+    pub done_at: NaiveDateTime,
+}
+
+impl DoneEntry {
+    pub fn new(todo: &Todo, now: NaiveDateTime, month_end: MonthEnd) -> Self {
+        DoneEntry {
+            id: todo.created_at.clone(),
+            text: todo.text.clone(),
+            kind: todo.kind.name().to_string(),
+            due: due_date(&todo.kind, now.date(), month_end),
+            done_at: now,
+        }
+    }
+
+    pub fn is_late(&self) -> bool {
+        self.due.is_some_and(|due| self.done_at.date() > due)
+    }
+}
+
+//周期性的待办取今天或之前最近的一次
+fn due_date(kind: &TodoKind, today: NaiveDate, month_end: MonthEnd) -> Option<NaiveDate> {
+    match kind {
+        TodoKind::General | TodoKind::Progress(_) => None,
+        TodoKind::Once(deadline) => Some(deadline.date),
+        kind => today
+            .iter_days()
+            .rev()
+            .take(366)
+            .find(|date| kind.occurs_on(*date, month_end)),
+    }
+}
+
+//从最早到today，每天一个计数
+pub fn done_per_day(history: &[DoneEntry], today: NaiveDate, days: u64) -> Vec<u64> {
+    (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - Days::new(offset);
+            history
+                .iter()
+                .filter(|entry| entry.done_at.date() == date)
+                .count() as u64
+        })
+        .collect()
+}
+
+//以today结尾，每7天一组，返回(完成数, 其中逾期完成数)
+pub fn done_per_week(history: &[DoneEntry], today: NaiveDate, weeks: u64) -> Vec<(u64, u64)> {
+    (0..weeks)
+        .rev()
+        .map(|offset| {
+            let end = today - Days::new(offset * 7);
+            let start = end - Days::new(6);
+            let entries = history
+                .iter()
+                .filter(|entry| (start..=end).contains(&entry.done_at.date()))
+                .collect::<Vec<_>>();
+            let late = entries.iter().filter(|entry| entry.is_late()).count();
+            (entries.len() as u64, late as u64)
+        })
+        .collect()
+}
+
+//只统计一次性的待办，周期性的创建时间没有意义
+pub fn average_time_to_complete(history: &[DoneEntry]) -> Option<TimeDelta> {
+    let durations = history
+        .iter()
+        .filter(|entry| matches!(entry.kind.as_str(), "General" | "Progress" | "Once"))
+        .filter_map(|entry| {
+            let created_at = NaiveDateTime::parse_from_str(&entry.id, "%Y-%m-%d %H:%M:%S").ok()?;
+            Some(entry.done_at - created_at)
+        })
+        .collect::<Vec<_>>();
+    if durations.is_empty() {
+        return None;
+    }
+    Some(durations.iter().sum::<TimeDelta>() / durations.len() as i32)
+}
+
+//连续完成的次数，今天还没完成不算中断
+pub fn streak(todo: &Todo, history: &[DoneEntry], today: NaiveDate, month_end: MonthEnd) -> u32 {
+    let done_dates = history
+        .iter()
+        .filter(|entry| entry.id == todo.created_at)
+        .filter_map(|entry| entry.due)
+        .collect::<Vec<_>>();
+    let mut streak = 0;
+    for date in today.iter_days().rev().take(366) {
+        if !todo.kind.occurs_on(date, month_end) {
+            continue;
+        }
+        if done_dates.contains(&date) {
+            streak += 1;
+        } else if date != today {
+            break;
+        }
+    }
+    streak
+}
+
+pub fn count_by_kind(todo_list: &[&Todo]) -> Vec<(&'static str, u64)> {
+    ["General", "Progress", "Once", "Week", "Month", "Recur"]
+        .into_iter()
+        .map(|name| {
+            let count = todo_list
+                .iter()
+                .filter(|todo| todo.kind.name() == name)
+                .count();
+            (name, count as u64)
+        })
+        .collect()
+}
+
+pub fn count_by_state(todo_list: &[&Todo]) -> Vec<(TodoState, u64)> {
+    [
+        TodoState::Expired,
+        TodoState::InProgress,
+        TodoState::UpComing,
+        TodoState::NoDeadline,
+    ]
+    .into_iter()
+    .map(|state| {
+        let count = todo_list.iter().filter(|todo| todo.state == state).count();
+        (state, count as u64)
+    })
+    .collect()
+}
+
+pub fn format_duration(duration: TimeDelta) -> String {
+    match (duration.num_days(), duration.num_hours() % 24) {
+        (0, 0) => format!("{}m", duration.num_minutes()),
+        (0, hours) => format!("{}h {}m", hours, duration.num_minutes() % 60),
+        (days, hours) => format!("{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(input: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn todo(input: &str, created_at: &str) -> Todo {
+        let (text, kind) = Todo::input_parse(input, at(created_at).date()).unwrap();
+        Todo {
+            text,
+            kind,
+            created_at: format!("{}:00", created_at),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stats_test() {
+        // 2025-01-15 是星期三
+        let today = "2025-01-15".parse().unwrap();
+        let report = todo("report -2025-01-10", "2025-01-06 09:00");
+        let milk = todo("milk", "2025-01-14 09:00");
+        let stretch = todo("stretch -daily", "2025-01-01 09:00");
+        let mut history = vec![
+            DoneEntry::new(&report, at("2025-01-12 09:00"), MonthEnd::Clamp),
+            DoneEntry::new(&milk, at("2025-01-14 21:00"), MonthEnd::Clamp),
+        ];
+        for day in ["2025-01-12", "2025-01-13", "2025-01-14"] {
+            history.push(DoneEntry::new(
+                &stretch,
+                at(&format!("{} 08:00", day)),
+                MonthEnd::Clamp,
+            ));
+        }
+        assert!(history[0].is_late());
+        assert!(!history[1].is_late());
+        assert_eq!(history[2].due, "2025-01-12".parse().ok());
+
+        assert_eq!(done_per_day(&history, today, 4), [2, 1, 2, 0]);
+        assert_eq!(done_per_week(&history, today, 2), [(0, 0), (5, 1)]);
+        // (6天 + 12小时) / 2
+        assert_eq!(
+            average_time_to_complete(&history),
+            Some(TimeDelta::hours(78))
+        );
+        assert_eq!(format_duration(TimeDelta::hours(78)), "3d 6h");
+        assert_eq!(format_duration(TimeDelta::minutes(90)), "1h 30m");
+
+        assert_eq!(streak(&stretch, &history, today, MonthEnd::Clamp), 3);
+        history.remove(2);
+        assert_eq!(streak(&stretch, &history, today, MonthEnd::Clamp), 2);
+
+        let todo_list = [&report, &milk, &stretch];
+        assert_eq!(count_by_kind(&todo_list)[0], ("General", 1));
+        assert_eq!(count_by_kind(&todo_list)[5], ("Recur", 1));
+    }
+}
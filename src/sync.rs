@@ -1,12 +1,15 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::{
-    io::{self, Read, Write},
+    io::{Read, Write},
     net::{TcpStream, UdpSocket},
     time::{self, Duration},
 };
 
-use crate::todo::Todo;
+use crate::{
+    error::{Error, Result},
+    todo::Todo,
+};
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SyncState {
@@ -14,9 +17,9 @@ pub struct SyncState {
     pub last_save_at: NaiveDateTime,
 }
 
+//Init表示第一次同步，服务器收下了本地的数据
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
 pub enum SyncAction {
-    //TODO
     #[default]
     Init,
     NoChange,
@@ -24,10 +27,54 @@ pub enum SyncAction {
     Download,
 }
 
+impl SyncAction {
+    pub fn print_info(&self) -> &'static str {
+        match self {
+            SyncAction::Init => "initialized",
+            SyncAction::NoChange => "no change",
+            SyncAction::Upload => "uploaded",
+            SyncAction::Download => "downloaded",
+        }
+    }
+
+    //服务器回复synced时根据本地的时间判断是否真的上传了修改
+    fn synced(local_sync_state: &SyncState) -> Self {
+        if local_sync_state.last_sync_at == NaiveDateTime::default() {
+            SyncAction::Init
+        } else if local_sync_state.last_save_at <= local_sync_state.last_sync_at {
+            SyncAction::NoChange
+        } else {
+            SyncAction::Upload
+        }
+    }
+}
+
+//同步在后台线程中进行，界面根据这个状态显示进度
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum SyncStatus {
+    #[default]
+    Idle,
+    Discovering,
+    Transferring,
+    Succeeded(SyncAction),
+    Failed(String),
+}
+
+impl SyncStatus {
+    pub fn is_running(&self) -> bool {
+        matches!(self, SyncStatus::Discovering | SyncStatus::Transferring)
+    }
+}
+
+//同步做了什么、同步后的状态和待办
+pub type SyncResult = (SyncAction, SyncState, Vec<Todo>);
+
 pub fn sync_app_data(
     mut local_sync_state: SyncState,
     local_todo_list: Vec<Todo>,
-) -> io::Result<Option<(SyncState, Vec<Todo>)>> {
+    progress: impl Fn(SyncStatus),
+) -> Result<Option<SyncResult>> {
+    progress(SyncStatus::Discovering);
     let socket = UdpSocket::bind("0.0.0.0:0")?;
     socket.set_broadcast(true)?;
     socket.set_read_timeout(Some(Duration::from_secs(2)))?;
@@ -38,6 +85,8 @@ pub fn sync_app_data(
         match socket.recv_from(&mut buf) {
             Ok((amt, src)) => {
                 if &buf[..amt] == b"lily" {
+                    progress(SyncStatus::Transferring);
+                    let action = SyncAction::synced(&local_sync_state);
                     let mut stream = TcpStream::connect(src)?;
                     local_sync_state.last_sync_at = chrono::Local::now().naive_local();
                     let local_todo_list_raw = serde_json::to_vec(&local_todo_list)?;
@@ -55,20 +104,45 @@ pub fn sync_app_data(
                         }
                         data.extend_from_slice(&buf[..amt]);
                     }
-                    if &data[..6] == b"synced" {
-                        return Ok(Some((local_sync_state, local_todo_list)));
+                    if data.starts_with(b"synced") {
+                        return Ok(Some((action, local_sync_state, local_todo_list)));
                     } else {
-                        let index = data.windows(4).position(|sep| sep == b"----").unwrap();
+                        //线程出错时同步状态会一直停在传输中，所以这里不能panic
+                        let index = data
+                            .windows(4)
+                            .position(|sep| sep == b"----")
+                            .ok_or_else(|| Error::Protocol("missing separator".to_string()))?;
                         let sync_state_raw = &data.split_off(index + 4);
                         let todo_list_raw = &data[..index];
-                        let sync_state = serde_json::from_slice::<SyncState>(&sync_state_raw)?;
-                        let todo_list = serde_json::from_slice::<Vec<Todo>>(&todo_list_raw)?;
-                        return Ok(Some((sync_state, todo_list)));
+                        let sync_state = serde_json::from_slice::<SyncState>(sync_state_raw)?;
+                        let todo_list = serde_json::from_slice::<Vec<Todo>>(todo_list_raw)?;
+                        return Ok(Some((SyncAction::Download, sync_state, todo_list)));
                     }
                 }
             }
             Err(_) => continue,
         }
     }
-    return Ok(None);
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synced_action_test() {
+        let at = |input: &str| NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M").unwrap();
+        let mut sync_state = SyncState {
+            last_save_at: at("2025-01-01 12:00"),
+            ..Default::default()
+        };
+        assert_eq!(SyncAction::synced(&sync_state), SyncAction::Init);
+        sync_state.last_sync_at = at("2025-01-01 12:30");
+        assert_eq!(SyncAction::synced(&sync_state), SyncAction::NoChange);
+        sync_state.last_save_at = at("2025-01-01 13:00");
+        assert_eq!(SyncAction::synced(&sync_state), SyncAction::Upload);
+        assert!(SyncStatus::Transferring.is_running());
+        assert!(!SyncStatus::Failed("no server found".to_string()).is_running());
+    }
 }
@@ -0,0 +1,229 @@
+use ratatui::style::{Color, Style};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::todo::{TodoKind, TodoState};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+//colors中的颜色会覆盖预设，颜色可以写成red、#ff0000或者0-255的编号
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub preset: ThemePreset,
+    pub colors: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub expired: Color,
+    pub in_progress: Color,
+    pub upcoming: Color,
+    pub no_deadline: Color,
+    pub general: Color,
+    pub progress: Color,
+    pub week: Color,
+    pub month: Color,
+    pub once: Color,
+    pub recur: Color,
+    pub insert: Color,
+    pub search: Color,
+    pub command: Color,
+    pub hint: Color,
+    pub error: Color,
+    pub alert: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset(ThemePreset::Dark)
+    }
+}
+
+impl Theme {
+    //设置了NO_COLOR环境变量时不使用任何颜色，见 https://no-color.org
+    pub fn new(config: &ThemeConfig) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Theme::build(config, no_color)
+    }
+
+    fn build(config: &ThemeConfig, no_color: bool) -> Self {
+        if no_color {
+            return Theme::plain();
+        }
+        let mut theme = Theme::preset(config.preset);
+        for (name, color) in config.colors.iter() {
+            let (Some(slot), Ok(color)) = (theme.slot(name), Color::from_str(color)) else {
+                continue;
+            };
+            *slot = color;
+        }
+        theme
+    }
+
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Theme {
+                expired: Color::Red,
+                in_progress: Color::Green,
+                upcoming: Color::Blue,
+                no_deadline: Color::Reset,
+                general: Color::Gray,
+                progress: Color::Cyan,
+                week: Color::Magenta,
+                month: Color::Yellow,
+                once: Color::LightBlue,
+                recur: Color::LightMagenta,
+                insert: Color::Cyan,
+                search: Color::Yellow,
+                command: Color::Magenta,
+                hint: Color::DarkGray,
+                error: Color::Red,
+                alert: Color::Yellow,
+            },
+            ThemePreset::Light => Theme {
+                expired: Color::Red,
+                in_progress: Color::Green,
+                upcoming: Color::Blue,
+                no_deadline: Color::Reset,
+                general: Color::DarkGray,
+                progress: Color::Blue,
+                week: Color::Magenta,
+                month: Color::Rgb(0x9a, 0x67, 0x00),
+                once: Color::Rgb(0x00, 0x5f, 0x87),
+                recur: Color::Rgb(0x87, 0x00, 0x87),
+                insert: Color::Blue,
+                search: Color::Rgb(0x9a, 0x67, 0x00),
+                command: Color::Magenta,
+                hint: Color::Gray,
+                error: Color::Red,
+                alert: Color::Rgb(0x9a, 0x67, 0x00),
+            },
+            ThemePreset::HighContrast => Theme {
+                expired: Color::LightRed,
+                in_progress: Color::LightGreen,
+                upcoming: Color::LightCyan,
+                no_deadline: Color::White,
+                general: Color::White,
+                progress: Color::LightCyan,
+                week: Color::LightMagenta,
+                month: Color::LightYellow,
+                once: Color::LightBlue,
+                recur: Color::LightMagenta,
+                insert: Color::LightCyan,
+                search: Color::LightYellow,
+                command: Color::LightMagenta,
+                hint: Color::White,
+                error: Color::LightRed,
+                alert: Color::LightYellow,
+            },
+        }
+    }
+
+    pub fn plain() -> Self {
+        Theme {
+            expired: Color::Reset,
+            in_progress: Color::Reset,
+            upcoming: Color::Reset,
+            no_deadline: Color::Reset,
+            general: Color::Reset,
+            progress: Color::Reset,
+            week: Color::Reset,
+            month: Color::Reset,
+            once: Color::Reset,
+            recur: Color::Reset,
+            insert: Color::Reset,
+            search: Color::Reset,
+            command: Color::Reset,
+            hint: Color::Reset,
+            error: Color::Reset,
+            alert: Color::Reset,
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> Option<&mut Color> {
+        let slot = match name {
+            "expired" => &mut self.expired,
+            "in_progress" => &mut self.in_progress,
+            "upcoming" => &mut self.upcoming,
+            "no_deadline" => &mut self.no_deadline,
+            "general" => &mut self.general,
+            "progress" => &mut self.progress,
+            "week" => &mut self.week,
+            "month" => &mut self.month,
+            "once" => &mut self.once,
+            "recur" => &mut self.recur,
+            "insert" => &mut self.insert,
+            "search" => &mut self.search,
+            "command" => &mut self.command,
+            "hint" => &mut self.hint,
+            "error" => &mut self.error,
+            "alert" => &mut self.alert,
+            _ => return None,
+        };
+        Some(slot)
+    }
+
+    pub fn state(&self, state: &TodoState) -> Style {
+        Style::new().fg(match state {
+            TodoState::Expired => self.expired,
+            TodoState::InProgress => self.in_progress,
+            TodoState::UpComing => self.upcoming,
+            TodoState::NoDeadline => self.no_deadline,
+        })
+    }
+
+    pub fn kind(&self, kind: &TodoKind) -> Style {
+        self.kind_named(kind.name())
+    }
+
+    //统计页面中只有类型名称，没有具体的TodoKind
+    pub fn kind_named(&self, name: &str) -> Style {
+        Style::new().fg(match name {
+            "Progress" => self.progress,
+            "Week" => self.week,
+            "Month" => self.month,
+            "Once" => self.once,
+            "Recur" => self.recur,
+            _ => self.general,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_test() {
+        let mut colors = BTreeMap::new();
+        colors.insert("expired".to_string(), "#ff8800".to_string());
+        colors.insert("week".to_string(), "201".to_string());
+        colors.insert("unknown".to_string(), "red".to_string());
+        colors.insert("recur".to_string(), "not a color".to_string());
+        let config = ThemeConfig {
+            preset: ThemePreset::Light,
+            colors,
+        };
+        assert_eq!(Theme::build(&config, true), Theme::plain());
+        let theme = Theme::build(&config, false);
+        let light = Theme::preset(ThemePreset::Light);
+        assert_eq!(theme.expired, Color::Rgb(0xff, 0x88, 0x00));
+        assert_eq!(theme.week, Color::Indexed(201));
+        assert_eq!(theme.recur, light.recur);
+        assert_eq!(theme.upcoming, light.upcoming);
+        assert_eq!(
+            theme.state(&TodoState::Expired),
+            Style::new().fg(Color::Rgb(0xff, 0x88, 0x00))
+        );
+        let config = serde_json::from_str::<ThemeConfig>(r#"{"preset": "high_contrast"}"#);
+        assert_eq!(config.unwrap().preset, ThemePreset::HighContrast);
+    }
+}
@@ -1,8 +1,27 @@
-use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
-use std::sync::LazyLock;
+use std::{fmt, str::FromStr};
 
-pub const TODAY: LazyLock<NaiveDateTime> = LazyLock::new(|| chrono::Local::now().naive_local());
+use crate::{date, recur::Recurrence};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnknownSuffix(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnknownSuffix(suffix) => write!(
+                f,
+                "cannot parse \"-{}\": try mon, 15, 2025-03-01, tomorrow, next fri, in 3 days, 12/25 or every 2 weeks, or write \\- and \\@ to keep - and @ in the text",
+                suffix
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Todo {
@@ -11,6 +30,8 @@ pub struct Todo {
     pub kind: TodoKind,
     pub state: TodoState,
     pub is_hidden: bool,
+    #[serde(default)]
+    pub list: String,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
@@ -37,97 +58,280 @@ impl TodoState {
 pub enum TodoKind {
     #[default]
     General,
-    Progress(String), //TODO 怎么解析？用@符号？
+    Progress(Progress),
     Week(Weekday),
     Month(u32),
-    Once(NaiveDate),
+    Once(Deadline),
+    Recur(Recurrence),
 }
 
 impl TodoKind {
     pub fn print_info(&self) -> String {
         match self {
             TodoKind::General => "General".to_string(),
-            TodoKind::Progress(s) => format!("Prog: {}", s),
+            TodoKind::Progress(p) => match p.ratio() {
+                Some(_) => format!("{} {}", p.bar(8), p.count_info()),
+                None => format!("Prog: {}", p),
+            },
             TodoKind::Week(w) => format!("Week: {}", w),
             TodoKind::Month(m) => format!("Month: {}", m),
             TodoKind::Once(d) => format!("Once: {}", d),
+            TodoKind::Recur(r) => format!("Recur: {}", r),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TodoKind::General => "General",
+            TodoKind::Progress(_) => "Progress",
+            TodoKind::Week(_) => "Week",
+            TodoKind::Month(_) => "Month",
+            TodoKind::Once(_) => "Once",
+            TodoKind::Recur(_) => "Recur",
+        }
+    }
+
+    //按类型排序时使用的顺序
+    pub fn rank(&self) -> u8 {
+        match self {
+            TodoKind::General => 0,
+            TodoKind::Progress(_) => 1,
+            TodoKind::Once(_) => 2,
+            TodoKind::Week(_) => 3,
+            TodoKind::Month(_) => 4,
+            TodoKind::Recur(_) => 5,
+        }
+    }
+
+    //General和Progress没有日期，不会出现在任何一天
+    pub fn occurs_on(&self, date: NaiveDate, month_end: MonthEnd) -> bool {
+        match self {
+            TodoKind::General | TodoKind::Progress(_) => false,
+            TodoKind::Week(weekday) => date.weekday() == *weekday,
+            TodoKind::Month(day) => month_end.matches(*day, date),
+            TodoKind::Once(deadline) => deadline.date == date,
+            TodoKind::Recur(recurrence) => recurrence.occurs_on(date),
+        }
+    }
+
+    pub fn next_occurrence(&self, today: NaiveDate, month_end: MonthEnd) -> Option<NaiveDate> {
+        match self {
+            TodoKind::General | TodoKind::Progress(_) => None,
+            TodoKind::Week(weekday) => today
+                .iter_days()
+                .take(7)
+                .find(|date| date.weekday() == *weekday),
+            TodoKind::Month(day) => month_end.next_occurrence(*day, today),
+            TodoKind::Once(deadline) => (deadline.date >= today).then_some(deadline.date),
+            TodoKind::Recur(recurrence) => recurrence.next_occurrence(today),
         }
     }
 }
 
+//帮助页面中展示的输入示例，测试中会逐条校验能否正确解析
+pub const SYNTAX_EXAMPLES: [(&str, &str); 25] = [
+    ("General", "buy milk #home"),
+    ("Week", "gym -mon"),
+    ("Month", "pay rent -15"),
+    ("Once", "dentist -2025-03-01"),
+    ("Once", "standup -2025-03-01 09:30"),
+    ("Once", "call mom -tomorrow"),
+    ("Once", "demo -next fri 14:00"),
+    ("Once", "renew passport -in 3 weeks"),
+    ("Once", "report -end of month"),
+    ("Once", "gifts -12/25"),
+    ("Recur", "stretch -daily"),
+    ("Recur", "water plants -every 3 days"),
+    ("Recur", "commute -weekdays"),
+    ("Recur", "1:1 -every 2 weeks on mon,thu"),
+    ("Recur", "book club -first mon of the month"),
+    ("Recur", "payroll -last fri"),
+    ("Recur", "birthday -yearly on mar 15"),
+    ("Recur", "course -weekly on tue until 2025-06-30"),
+    ("Recur", "sprint -every 2 weeks from 2025-01-06"),
+    ("Progress", "read book @3/10"),
+    ("Progress", "thesis @45%"),
+    ("Progress", "novel @chapter 4 of 12"),
+    ("General", "follow-up with Bob"),
+    ("General", "pay \\-50 to \\@alice"),
+    ("Once", "pros \\- cons -next fri"),
+];
+
 impl Todo {
-    pub fn new(input: &str) -> Self {
-        let (todo_text, todo_kind) = Self::input_parse(&input);
+    pub fn new(input: &str) -> Result<Self, ParseError> {
+        let today = chrono::Local::now().date_naive();
+        let (todo_text, todo_kind) = Self::input_parse(input, today)?;
         let mut todo = Todo {
-            text: todo_text.to_string(),
+            text: todo_text,
             created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             kind: todo_kind,
             state: TodoState::default(),
             is_hidden: bool::default(),
+            list: String::default(),
         };
-        todo.state_check();
-        todo
-    }
-
-    fn input_parse(input: &str) -> (&str, TodoKind) {
-        match input.split_once('-') {
-            Some((text, suffix)) => {
-                if !text.is_empty() && !suffix.is_empty() {
-                    let todo_text = text.trim();
-                    let suffix = suffix.trim();
-                    let mut todo_kind = TodoKind::default();
-                    if let Ok(weekday) = suffix.parse::<chrono::Weekday>() {
-                        todo_kind = TodoKind::Week(weekday);
-                        return (todo_text, todo_kind);
-                    }
-                    if let Ok(day) = suffix.parse::<u32>() {
-                        if day > 0 && day < 32 {
-                            todo_kind = TodoKind::Month(day);
-                            return (todo_text, todo_kind);
-                        }
-                    }
-                    if let Ok(date) = suffix.parse::<NaiveDate>() {
-                        todo_kind = TodoKind::Once(date);
-                        return (todo_text, todo_kind);
-                    }
-                    (input.trim(), todo_kind)
-                } else {
-                    (input.trim(), TodoKind::default())
-                }
+        //按默认的月末处理方式计算，界面中会按配置重新检查
+        todo.state_check(MonthEnd::default());
+        Ok(todo)
+    }
+
+    //只解析类型部分，例如"-fri"、"@3/10"，"general"或空字符串表示没有期限，省略-时按日期解析
+    pub fn parse_kind(suffix: &str) -> Result<TodoKind, ParseError> {
+        let suffix = suffix.trim();
+        if suffix.is_empty() || suffix.eq_ignore_ascii_case("general") {
+            return Ok(TodoKind::General);
+        }
+        let input = if suffix.starts_with(['-', '@']) {
+            format!("_ {}", suffix)
+        } else {
+            format!("_ -{}", suffix)
+        };
+        let today = chrono::Local::now().date_naive();
+        match Todo::input_parse(&input, today)? {
+            (_, TodoKind::General) => Err(ParseError::UnknownSuffix(suffix.to_string())),
+            (_, kind) => Ok(kind),
+        }
+    }
+
+    pub(crate) fn input_parse(
+        input: &str,
+        today: NaiveDate,
+    ) -> Result<(String, TodoKind), ParseError> {
+        match Self::split_input(input) {
+            (text, Some(('-', suffix))) => {
+                let todo_kind =
+                    Self::suffix_parse(&suffix, today).ok_or(ParseError::UnknownSuffix(suffix))?;
+                Ok((text, todo_kind))
             }
-            None => match input.split_once('@') {
-                Some((text, suffix)) if !text.is_empty() && !suffix.is_empty() => {
-                    let todo_text = text.trim();
-                    let progress = suffix.trim();
-                    let todo_kind = TodoKind::Progress(progress.to_string());
-                    (todo_text, todo_kind)
+            (text, Some((_, progress))) => {
+                let todo_kind = TodoKind::Progress(Progress::from(progress));
+                Ok((text, todo_kind))
+            }
+            (text, None) => Ok((text, TodoKind::default())),
+        }
+    }
+
+    //只有前面是空白的-和@才算分隔符，内容中需要的话可以写成\-和\@
+    pub fn split_input(input: &str) -> (String, Option<(char, String)>) {
+        let mut text = String::new();
+        let mut delimiters = vec![];
+        let mut prev_is_space = false;
+        let mut chars = input.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some((_, '-' | '@'))) => {
+                    let (_, escaped) = chars.next().unwrap();
+                    text.push(escaped);
+                    prev_is_space = false;
+                    continue;
                 }
-                _ => (input.trim(), TodoKind::default()),
-            },
+                '-' | '@' if prev_is_space => delimiters.push((c, index, text.len())),
+                _ => {}
+            }
+            text.push(c);
+            prev_is_space = c.is_whitespace();
         }
+        //有-时优先按截止日期解析，否则按进度解析
+        let delimiter = delimiters
+            .iter()
+            .rev()
+            .find(|(c, ..)| *c == '-')
+            .or(delimiters.last());
+        if let Some(&(c, index, text_len)) = delimiter {
+            let suffix = input[index + 1..]
+                .trim()
+                .replace("\\-", "-")
+                .replace("\\@", "@");
+            let todo_text = text[..text_len].trim();
+            if !suffix.is_empty() && !todo_text.is_empty() {
+                return (todo_text.to_string(), Some((c, suffix)));
+            }
+        }
+        (text.trim().to_string(), None)
     }
 
-    pub fn state_check(&mut self) {
+    //把待办还原成输入框中的文本，用于修改
+    pub fn to_input(&self) -> String {
+        let text = Self::escape(&self.text, false);
         match self.kind {
-            TodoKind::Once(date) => {
-                if date == TODAY.date() {
-                    self.state = TodoState::InProgress;
-                } else if date < TODAY.date() {
+            TodoKind::General => text,
+            TodoKind::Week(week) => format!("{} - {}", text, week),
+            TodoKind::Month(day) => format!("{} - {}", text, day),
+            TodoKind::Once(date) => format!("{} - {}", text, date),
+            TodoKind::Recur(ref recurrence) => format!("{} - {}", text, recurrence),
+            TodoKind::Progress(ref progress) => {
+                format!("{} @ {}", text, Self::escape(&progress.to_string(), true))
+            }
+        }
+    }
+
+    //后缀前面总有空格，开头的-和@也要转义
+    fn escape(input: &str, mut prev_is_space: bool) -> String {
+        let mut escaped = String::new();
+        for c in input.chars() {
+            if prev_is_space && matches!(c, '-' | '@') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            prev_is_space = c.is_whitespace();
+        }
+        escaped
+    }
+
+    fn suffix_parse(suffix: &str, today: NaiveDate) -> Option<TodoKind> {
+        if let Ok(weekday) = suffix.parse::<chrono::Weekday>() {
+            return Some(TodoKind::Week(weekday));
+        }
+        if let Ok(day) = suffix.parse::<u32>() {
+            return (day > 0 && day < 32).then_some(TodoKind::Month(day));
+        }
+        if let Ok(deadline) = suffix.parse::<Deadline>() {
+            return Some(TodoKind::Once(deadline));
+        }
+        if let Some(deadline) = date::parse_relative(suffix, today) {
+            return Some(TodoKind::Once(deadline));
+        }
+        Recurrence::parse(suffix, today).map(TodoKind::Recur)
+    }
+
+    pub fn state_check(&mut self, month_end: MonthEnd) {
+        self.state_check_at(chrono::Local::now().naive_local(), month_end);
+    }
+
+    pub fn state_check_at(&mut self, now: NaiveDateTime, month_end: MonthEnd) {
+        let today = now.date();
+        match self.kind {
+            TodoKind::Once(deadline) => {
+                if deadline.date == today {
+                    //设置了具体时间的待办过了时间点就算过期
+                    self.state = match deadline.time {
+                        Some(time) if time < now.time() => TodoState::Expired,
+                        _ => TodoState::InProgress,
+                    };
+                } else if deadline.date < today {
                     self.state = TodoState::Expired;
                 } else {
                     self.state = TodoState::UpComing;
                 }
             }
             TodoKind::Week(weekday) => {
-                if weekday == TODAY.weekday() {
+                if weekday == today.weekday() {
                     self.state = TodoState::InProgress;
                 } else {
                     self.state = TodoState::UpComing;
                 }
             }
             TodoKind::Month(day) => {
-                if day == TODAY.day() {
+                if month_end.matches(day, today) {
+                    self.state = TodoState::InProgress;
+                } else {
+                    self.state = TodoState::UpComing;
+                }
+            }
+            TodoKind::Recur(ref recurrence) => {
+                if recurrence.occurs_on(today) {
                     self.state = TodoState::InProgress;
+                } else if recurrence.is_finished(today) {
+                    self.state = TodoState::Expired;
                 } else {
                     self.state = TodoState::UpComing;
                 }
@@ -135,15 +339,258 @@ impl Todo {
             _ => {}
         }
     }
+
+    //只有设置了具体时间的一次性待办才需要提醒
+    pub fn remind_at(&self, minutes_before: i64) -> Option<NaiveDateTime> {
+        match self.kind {
+            TodoKind::Once(Deadline {
+                date,
+                time: Some(time),
+            }) => Some(date.and_time(time) - chrono::Duration::minutes(minutes_before)),
+            _ => None,
+        }
+    }
+
     pub fn reset_hidden_flag(&mut self) {
         self.is_hidden = false;
     }
+
+    pub fn is_visible_in(&self, list: &str) -> bool {
+        !self.is_hidden && self.list == list
+    }
+
+    pub fn progress_step(&mut self, forward: bool) {
+        if let TodoKind::Progress(ref mut progress) = self.kind {
+            progress.step(forward);
+        }
+    }
+}
+
+//月度待办的日期超过当月天数时的处理方式
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum MonthEnd {
+    //提前到当月最后一天
+    #[default]
+    Clamp,
+    //当月跳过
+    Skip,
+    //顺延到下个月1号
+    RollOver,
+}
+
+impl MonthEnd {
+    pub fn matches(&self, day: u32, date: NaiveDate) -> bool {
+        let days_in_month = days_in_month(date);
+        if day <= days_in_month {
+            return date.day() == day
+                || (*self == MonthEnd::RollOver && date.day() == 1 && Self::overflowed(day, date));
+        }
+        match self {
+            MonthEnd::Clamp => date.day() == days_in_month,
+            MonthEnd::Skip => false,
+            MonthEnd::RollOver => date.day() == 1 && Self::overflowed(day, date),
+        }
+    }
+
+    pub fn next_occurrence(&self, day: u32, from: NaiveDate) -> Option<NaiveDate> {
+        //跳过的情况最多连续两个月
+        from.iter_days()
+            .take(31 * 3)
+            .find(|date| self.matches(day, *date))
+    }
+
+    fn overflowed(day: u32, date: NaiveDate) -> bool {
+        date.pred_opt()
+            .is_some_and(|last_month| day > days_in_month(last_month))
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+//截止日期统一按字符串存储，兼容旧版本只有日期的数据
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(try_from = "String", into = "String")]
+pub struct Deadline {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+impl FromStr for Deadline {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+            Ok(date_time) => Ok(Deadline {
+                date: date_time.date(),
+                time: Some(date_time.time()),
+            }),
+            Err(_) => Ok(Deadline {
+                date: s.parse::<NaiveDate>()?,
+                time: None,
+            }),
+        }
+    }
+}
+
+impl TryFrom<String> for Deadline {
+    type Error = chrono::ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Deadline> for String {
+    fn from(value: Deadline) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(f, "{} {}", self.date, time.format("%H:%M")),
+            None => write!(f, "{}", self.date),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum ProgressFormat {
+    #[default]
+    Fraction,
+    Percent,
+    Of(String),
+    Text(String),
+}
+
+//进度统一按字符串存储，兼容旧版本数据中的任意文本
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(from = "String", into = "String")]
+pub struct Progress {
+    pub current: u32,
+    pub total: u32,
+    pub format: ProgressFormat,
+}
+
+impl Progress {
+    fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Some(percent) = input.strip_suffix('%') {
+            let current = percent.trim().parse::<u32>().ok()?;
+            return (current <= 100).then_some(Progress {
+                current,
+                total: 100,
+                format: ProgressFormat::Percent,
+            });
+        }
+        if let Some((current, total)) = input.split_once('/') {
+            let current = current.trim().parse::<u32>().ok()?;
+            let total = total.trim().parse::<u32>().ok()?;
+            return (total > 0 && current <= total).then_some(Progress {
+                current,
+                total,
+                format: ProgressFormat::Fraction,
+            });
+        }
+        // 形如 "chapter 4 of 12"，单位可以省略
+        let words = input.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            [unit @ .., current, of, total] if of.eq_ignore_ascii_case("of") => {
+                let current = current.parse::<u32>().ok()?;
+                let total = total.parse::<u32>().ok()?;
+                (total > 0 && current <= total).then_some(Progress {
+                    current,
+                    total,
+                    format: ProgressFormat::Of(unit.join(" ")),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn ratio(&self) -> Option<f64> {
+        match self.format {
+            ProgressFormat::Text(_) => None,
+            _ => Some(self.current as f64 / self.total as f64),
+        }
+    }
+
+    //百分比形式每次调整5%，其余形式每次调整1
+    pub fn step(&mut self, forward: bool) {
+        let step = match self.format {
+            ProgressFormat::Text(_) => return,
+            ProgressFormat::Percent => 5,
+            _ => 1,
+        };
+        self.current = if forward {
+            self.current.saturating_add(step).min(self.total)
+        } else {
+            self.current.saturating_sub(step)
+        };
+    }
+
+    pub fn count_info(&self) -> String {
+        match self.format {
+            ProgressFormat::Percent => format!("{}%", self.current),
+            _ => format!("{}/{}", self.current, self.total),
+        }
+    }
+
+    pub fn bar(&self, width: usize) -> String {
+        let filled = (self.ratio().unwrap_or(0.0) * width as f64).round() as usize;
+        format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+    }
+}
+
+impl From<String> for Progress {
+    fn from(value: String) -> Self {
+        Progress::parse(&value).unwrap_or(Progress {
+            format: ProgressFormat::Text(value),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<Progress> for String {
+    fn from(value: Progress) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            ProgressFormat::Fraction => write!(f, "{}/{}", self.current, self.total),
+            ProgressFormat::Percent => write!(f, "{}%", self.current),
+            ProgressFormat::Of(ref unit) if unit.is_empty() => {
+                write!(f, "{} of {}", self.current, self.total)
+            }
+            ProgressFormat::Of(ref unit) => {
+                write!(f, "{} {} of {}", unit, self.current, self.total)
+            }
+            ProgressFormat::Text(ref text) => write!(f, "{}", text),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 12, 1).unwrap()
+    }
+
     #[test]
     fn todo_kind_parse_test() {
         let input = [
@@ -155,19 +602,245 @@ mod tests {
             "do something awesome! -abcdefg",
         ];
         assert_eq!(
-            Todo::input_parse(input[0]).1,
-            TodoKind::Once(NaiveDate::from_ymd_opt(2024, 12, 22).unwrap())
+            Todo::input_parse(input[0], today()).unwrap().1,
+            TodoKind::Once(Deadline {
+                date: NaiveDate::from_ymd_opt(2024, 12, 22).unwrap(),
+                time: None
+            })
         );
         assert_eq!(
-            Todo::input_parse(input[1]).1,
+            Todo::input_parse(input[1], today()).unwrap().1,
             TodoKind::Week(chrono::Weekday::Mon)
         );
-        assert_eq!(Todo::input_parse(input[2]).1, TodoKind::Month(24));
         assert_eq!(
-            Todo::input_parse(input[3]).1,
+            Todo::input_parse(input[2], today()).unwrap().1,
+            TodoKind::Month(24)
+        );
+        assert_eq!(
+            Todo::input_parse(input[3], today()).unwrap().1,
             TodoKind::Week(chrono::Weekday::Sat)
         );
-        assert_eq!(Todo::input_parse(input[4]).1, TodoKind::default());
-        assert_eq!(Todo::input_parse(input[5]).1, TodoKind::default());
+        assert_eq!(
+            Todo::input_parse(input[4], today()),
+            Err(ParseError::UnknownSuffix("243".to_string()))
+        );
+        assert_eq!(
+            Todo::input_parse(input[5], today()),
+            Err(ParseError::UnknownSuffix("abcdefg".to_string()))
+        );
+        assert_eq!(
+            Todo::input_parse("do something awesome! -next fri", today()),
+            Ok((
+                "do something awesome!".to_string(),
+                TodoKind::Once(Deadline {
+                    date: NaiveDate::from_ymd_opt(2024, 12, 6).unwrap(),
+                    time: None
+                })
+            ))
+        );
+        assert!(matches!(
+            Todo::input_parse("water the plants -every 3 days", today())
+                .unwrap()
+                .1,
+            TodoKind::Recur(_)
+        ));
+    }
+
+    #[test]
+    fn syntax_examples_test() {
+        for (kind, example) in SYNTAX_EXAMPLES {
+            let (_, todo_kind) = Todo::input_parse(example, today()).unwrap();
+            assert!(format!("{:?}", todo_kind).starts_with(kind), "{}", example);
+        }
+    }
+
+    #[test]
+    fn input_escape_test() {
+        assert_eq!(
+            Todo::input_parse("follow-up with Bob -mon", today()),
+            Ok((
+                "follow-up with Bob".to_string(),
+                TodoKind::Week(chrono::Weekday::Mon)
+            ))
+        );
+        assert_eq!(
+            Todo::input_parse("pay \\-50 to \\@alice", today()),
+            Ok(("pay -50 to @alice".to_string(), TodoKind::General))
+        );
+        assert_eq!(
+            Todo::input_parse("a - b -15", today()),
+            Ok(("a - b".to_string(), TodoKind::Month(15)))
+        );
+        assert_eq!(
+            Todo::split_input("read @ chapter 4 of 12"),
+            (
+                "read".to_string(),
+                Some(('@', "chapter 4 of 12".to_string()))
+            )
+        );
+        assert_eq!(Todo::split_input("-mon"), ("-mon".to_string(), None));
+        //内容中单独的-会被当成分隔符，错误信息提示转义的写法
+        let err = Todo::input_parse("pros - cons", today()).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("write \\- and \\@ to keep - and @ in the text"));
+        assert_eq!(
+            Todo::input_parse("pros \\- cons", today()),
+            Ok(("pros - cons".to_string(), TodoKind::General))
+        );
+        let todo = Todo {
+            text: "pay -50 to @alice".to_string(),
+            kind: TodoKind::Month(15),
+            ..Default::default()
+        };
+        assert_eq!(todo.to_input(), "pay \\-50 to \\@alice - 15");
+        assert_eq!(
+            Todo::input_parse(&todo.to_input(), today()),
+            Ok((todo.text.clone(), todo.kind.clone()))
+        );
+        let todo = Todo {
+            text: "x".to_string(),
+            kind: TodoKind::Progress(Progress::from("a - b @c".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(todo.to_input(), "x @ a \\- b \\@c");
+        assert_eq!(
+            Todo::input_parse(&todo.to_input(), today()),
+            Ok((todo.text.clone(), todo.kind.clone()))
+        );
+    }
+
+    #[test]
+    fn deadline_time_test() {
+        let (_, kind) = Todo::input_parse("meeting -2025-03-01 14:30", today()).unwrap();
+        let deadline = Deadline {
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            time: NaiveTime::from_hms_opt(14, 30, 0),
+        };
+        assert_eq!(kind, TodoKind::Once(deadline));
+        let kind = serde_json::from_str::<TodoKind>(r#"{"Once":"2025-03-01"}"#).unwrap();
+        assert_eq!(kind.print_info(), "Once: 2025-03-01");
+        let mut todo = Todo {
+            kind: TodoKind::Once(deadline),
+            ..Default::default()
+        };
+        let at = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+        todo.state_check_at(at("2025-03-01 14:00"), MonthEnd::Clamp);
+        assert_eq!(todo.state, TodoState::InProgress);
+        todo.state_check_at(at("2025-03-01 14:31"), MonthEnd::Clamp);
+        assert_eq!(todo.state, TodoState::Expired);
+        todo.state_check_at(at("2025-02-28 23:00"), MonthEnd::Clamp);
+        assert_eq!(todo.state, TodoState::UpComing);
+        assert_eq!(todo.remind_at(10), Some(at("2025-03-01 14:20")));
+    }
+
+    #[test]
+    fn month_end_test() {
+        let date = |input: &str| input.parse::<NaiveDate>().unwrap();
+        // 闰年2月
+        assert!(MonthEnd::Clamp.matches(31, date("2024-02-29")));
+        assert!(MonthEnd::Clamp.matches(29, date("2024-02-29")));
+        assert!(!MonthEnd::Clamp.matches(28, date("2024-02-29")));
+        assert!(!MonthEnd::Skip.matches(30, date("2024-02-29")));
+        assert!(MonthEnd::RollOver.matches(30, date("2024-03-01")));
+        assert!(!MonthEnd::RollOver.matches(29, date("2024-03-01")));
+        // 平年2月
+        assert!(MonthEnd::Clamp.matches(29, date("2025-02-28")));
+        assert!(MonthEnd::RollOver.matches(29, date("2025-03-01")));
+        assert!(MonthEnd::RollOver.matches(29, date("2025-03-29")));
+        // 小月
+        assert!(MonthEnd::Clamp.matches(31, date("2025-04-30")));
+        assert!(!MonthEnd::Clamp.matches(31, date("2025-05-30")));
+        assert!(MonthEnd::Clamp.matches(31, date("2025-12-31")));
+        assert_eq!(
+            MonthEnd::Skip.next_occurrence(31, date("2025-02-01")),
+            Some(date("2025-03-31"))
+        );
+        assert_eq!(
+            MonthEnd::Clamp.next_occurrence(30, date("2025-02-01")),
+            Some(date("2025-02-28"))
+        );
+        assert_eq!(
+            MonthEnd::RollOver.next_occurrence(31, date("2025-04-02")),
+            Some(date("2025-05-01"))
+        );
+        assert_eq!(
+            MonthEnd::Clamp.next_occurrence(31, date("2024-02-01")),
+            Some(date("2024-02-29"))
+        );
+    }
+
+    #[test]
+    fn progress_parse_test() {
+        let progress = |input: &str| Progress::from(input.to_string());
+        assert_eq!(
+            progress("3/10"),
+            Progress {
+                current: 3,
+                total: 10,
+                format: ProgressFormat::Fraction
+            }
+        );
+        assert_eq!(
+            progress("45%"),
+            Progress {
+                current: 45,
+                total: 100,
+                format: ProgressFormat::Percent
+            }
+        );
+        assert_eq!(
+            progress("chapter 4 of 12"),
+            Progress {
+                current: 4,
+                total: 12,
+                format: ProgressFormat::Of("chapter".to_string())
+            }
+        );
+        assert_eq!(progress("chapter 4 of 12").to_string(), "chapter 4 of 12");
+        assert_eq!(
+            progress("11/10").format,
+            ProgressFormat::Text("11/10".to_string())
+        );
+        assert_eq!(progress("half done").ratio(), None);
+        // 旧版本直接把进度存成字符串，需要能正常读取
+        let kind = serde_json::from_str::<TodoKind>(r#"{"Progress":"2/4"}"#).unwrap();
+        assert_eq!(kind, TodoKind::Progress(progress("2/4")));
+        assert_eq!(
+            serde_json::to_string(&kind).unwrap(),
+            r#"{"Progress":"2/4"}"#
+        );
+    }
+
+    #[test]
+    fn progress_step_test() {
+        let mut progress = Progress::from("9/10".to_string());
+        progress.step(true);
+        progress.step(true);
+        assert_eq!(progress.current, 10);
+        let mut progress = Progress::from("3%".to_string());
+        progress.step(false);
+        assert_eq!(progress.current, 0);
+        progress.step(true);
+        assert_eq!(progress.bar(4), "░░░░");
+        assert_eq!(progress.count_info(), "5%");
+        let mut progress = Progress::from("4294967295/4294967295".to_string());
+        progress.step(true);
+        assert_eq!(progress.current, u32::MAX);
+    }
+
+    #[test]
+    fn parse_kind_test() {
+        assert_eq!(Todo::parse_kind("general"), Ok(TodoKind::General));
+        assert_eq!(Todo::parse_kind(""), Ok(TodoKind::General));
+        assert_eq!(Todo::parse_kind("-fri"), Ok(TodoKind::Week(Weekday::Fri)));
+        assert!(matches!(
+            Todo::parse_kind("@3/10"),
+            Ok(TodoKind::Progress(_))
+        ));
+        assert!(Todo::parse_kind("-someday").is_err());
+        assert_eq!(Todo::parse_kind("fri"), Ok(TodoKind::Week(Weekday::Fri)));
+        assert!(Todo::parse_kind("someday").is_err());
+        assert!(Todo::parse_kind("-").is_err());
     }
 }